@@ -1,28 +1,63 @@
+use std::cmp;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
 use pg::*;
+use cancel::Cancellation;
 use strategies::Strategy;
+use zielonka;
 
-static mut global_iterations: u64 = 0;
+/// How `prog` computes the least measure that is large enough for the measure of a successor.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProgSort {
+    /// Truncate the measure of the successor at the priority and increase it once if the priority is odd.
+    ClosedForm,
+    /// Increase the zero measure one step at a time until it is large enough, as counted by `nr_of_steps`.
+    Incremental
+}
 
 // slide 22
-fn prog(game: &Game, progress: &Progress, v: &Node, w: &Node) -> MeasureT {
-    let m_w = progress.measure(&w.id);
+fn prog(game: &Game, progress: &Progress, v: u32, w: u32, prog_sort: ProgSort, nr_of_steps: &mut u64) -> MeasureT {
+    prog_measure(game, progress.player, v, progress.measure(w), prog_sort, nr_of_steps)
+}
+
+/// Returns `prog` of the node for the player, given the measure of the successor.
+///
+/// Every increase of the measure by the incremental `prog` is counted as a step.
+fn prog_measure(game: &Game,
+                player: Owner,
+                v: u32,
+                m_w: &MeasureT,
+                prog_sort: ProgSort,
+                nr_of_steps: &mut u64) -> MeasureT {
     if m_w == &MeasureT::Top {
         return MeasureT::Top;
     }
 
+    let v_prio = game.prio(v) as usize;
+    let prio_of_player = Owner::of_prio(v_prio as u32) == player;
+
+    if prog_sort == ProgSort::ClosedForm {
+        return if prio_of_player {
+            m_w.truncate(v_prio)
+        } else {
+            m_w.inc_up_to(v_prio, game, player)
+        };
+    }
+
     let mut m = game.new_measure();
-    let v_prio = v.prio as usize;
-    let prio_is_even = v_prio % 2 == 0;
 
-    if prio_is_even {
+    if prio_of_player {
         while m.lt(m_w, v_prio) {
-            m = m.inc(game);
-            unsafe { global_iterations+=1; }
+            m = m.inc(game, player);
+            *nr_of_steps += 1;
         }
     } else {
         while m.le(m_w, v_prio) {
-            m = m.inc(game);
-            unsafe { global_iterations+=1; }
+            m = m.inc(game, player);
+            *nr_of_steps += 1;
         }
     }
 
@@ -30,69 +65,500 @@ fn prog(game: &Game, progress: &Progress, v: &Node, w: &Node) -> MeasureT {
 }
 
 // slide 26
-fn lift(game: &Game, v: &Node, progress: &Progress) -> Progress {
-    let mut progress_val = progress.0.clone();
+/// Lifts the measure of the node in place and returns `true` if it increased.
+///
+/// The player of the progress measure chooses the least `prog` of its successors, its opponent the greatest.
+fn lift(game: &Game, v: u32, progress: &mut Progress, prog_sort: ProgSort, nr_of_steps: &mut u64) -> bool {
+    let val = {
+        let edges = game.succ(v).iter().map(|w| prog(game, progress, v, *w, prog_sort, nr_of_steps));
+        if game.owner(v) == progress.player {
+            edges.min().unwrap()
+        } else {
+            edges.max().unwrap()
+        }
+    };
+    if &val > progress.measure(v) {
+        progress.measures[v as usize] = val;
+        return true;
+    }
+
+    false
+}
 
-    let edges = v.succ.iter().map(|w| prog(game, progress, v, game.node(w)));
-    let val = if v.owner == Owner::Even {
+/// Lifts the measure of the node in a table shared between threads and returns `true` if it increased.
+///
+/// Every measure in the table is read and replaced as a whole, and is only replaced by a greater measure. The steps of
+/// the incremental `prog` are counted by the thread.
+fn lift_shared(game: &Game,
+               v: u32,
+               player: Owner,
+               measures: &[RwLock<MeasureT>],
+               prog_sort: ProgSort,
+               nr_of_steps: &mut u64) -> bool {
+    let val = {
+        let edges = game.succ(v)
+            .iter()
+            .map(|w| prog_measure(game, player, v, &measures[*w as usize].read().unwrap(), prog_sort, nr_of_steps));
+        if game.owner(v) == player {
             edges.min().unwrap()
         } else {
             edges.max().unwrap()
-        };
-    if &val > progress_val.get(&v.id).unwrap() {
-        progress_val.insert(v.id, val);
+        }
+    };
+
+    let mut m_v = measures[v as usize].write().unwrap();
+    if val > *m_v {
+        *m_v = val;
+        return true;
+    }
+
+    false
+}
+
+/// Extracts the winning strategy of the player of the final progress measure.
+///
+/// On every node of the player with a measure other than `Top` the successor with the least `prog` is chosen.
+fn measure_strategy(game: &Game, progress: &Progress) -> PositionalStrategy {
+    let mut strategy = PositionalStrategy::new();
+
+    for v in game.nodes() {
+        if game.owner(v) != progress.player || progress.measure(v) == &MeasureT::Top {
+            continue;
+        }
+
+        let w = game.succ(v)
+            .iter()
+            .min_by_key(|w| prog(game, progress, v, **w, ProgSort::ClosedForm, &mut 0))
+            .unwrap();
+        strategy.0.insert(v, *w);
+    }
+
+    strategy
+}
+
+/// Extracts the winning strategy of the player on its winning set, which is not given by a progress measure.
+///
+/// A successor with measure `Top` is not necessarily a winning choice for the opponent of the measure, so the strategy
+/// is not derived from the measures at all. Instead, the subgame induced by the winning set of the player, which the
+/// player wins entirely, is solved again with Zielonka's algorithm. The cost of this is not bounded by that of the
+/// progress measures and is not counted in their statistics, which is why it is only done if the strategies of both
+/// players are needed. The measures of both players, see `small_progress_measures_two_sided`, yield both strategies
+/// without solving again.
+pub fn subgame_strategy(game: &Game, player: Owner, won: &HashSet<u32>) -> PositionalStrategy {
+    let result = zielonka::zielonka_subgame(game, won);
+
+    match player {
+        Owner::Even => result.strategy_even,
+        Owner::Odd  => result.strategy_odd,
     }
-    
-    Progress(progress_val)
 }
 
 pub struct SpmResult {
-    pub prog: Progress,
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    /// The progress measures of `Even`, if these were computed.
+    pub progress_even: Option<Progress>,
+    /// The progress measures of `Odd`, if these were computed.
+    pub progress_odd: Option<Progress>,
+    /// The strategy of `Even`, which is empty if only the progress measures of `Odd` were computed.
+    pub strategy_even: PositionalStrategy,
+    /// The strategy of `Odd`, which is empty if only the progress measures of `Even` were computed.
+    pub strategy_odd: PositionalStrategy,
+    /// The number of sweeps over all nodes, or the number of lifts that increased a measure for the worklist variant.
     pub nr_of_iterations: u64,
+    /// The number of attempts to lift a node.
     pub nr_of_subiterations: u64,
-    pub global_iterations: u64
+    /// The number of steps taken by the incremental `prog`, which is zero for `ProgSort::ClosedForm`.
+    pub nr_of_steps: u64
 }
-pub fn small_progress_measures(game: &Game, strategy: &Strategy) -> SpmResult {
-    let mut progress = game.new_progress();
+
+/// The statistics of small progress measures that were cancelled before the measures were stable.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpmTimedOut {
+    pub nr_of_iterations: u64,
+    pub nr_of_subiterations: u64,
+    pub nr_of_steps: u64
+}
+
+/// Creates the result from the stable progress measure of a single player.
+///
+/// The strategy of the player follows from its measure. The strategy of the opponent is left empty, as it can only be
+/// obtained by solving its winning set, see `subgame_strategy`.
+fn spm_result(game: &Game,
+              progress: Progress,
+              nr_of_iterations: u64,
+              nr_of_subiterations: u64,
+              nr_of_steps: u64) -> SpmResult {
+    // println!("Number of iterations: {}", nr_of_iterations);
+    // println!("Number of sub-iterations: {}", nr_of_subiterations);
+    let player = progress.player;
+    let won_even = progress.winning_set(Owner::Even);
+    let won_odd = progress.winning_set(Owner::Odd);
+    let strategy = measure_strategy(game, &progress);
+
+    let (strategy_even, strategy_odd, progress_even, progress_odd) = match player {
+        Owner::Even => (strategy, PositionalStrategy::new(), Some(progress), None),
+        Owner::Odd  => (PositionalStrategy::new(), strategy, None, Some(progress)),
+    };
+
+    SpmResult {
+        won_even,
+        won_odd,
+        progress_even,
+        progress_odd,
+        strategy_even,
+        strategy_odd,
+        nr_of_iterations,
+        nr_of_subiterations,
+        nr_of_steps
+    }
+}
+
+/// Lifts every node in the order of the vertices until its measure is stable, and returns `true` if any increased.
+///
+/// The sweep stops before the next node once the token is cancelled, so the caller has to check the token as well.
+fn sweep(game: &Game,
+         vertices: &[u32],
+         progress: &mut Progress,
+         prog_sort: ProgSort,
+         cancel: &Cancellation,
+         nr_of_subiterations: &mut u64,
+         nr_of_steps: &mut u64) -> bool {
+    let mut any_change = false;
+
+    for &v in vertices {
+        if cancel.is_cancelled() {
+            break;
+        }
+
+        loop {
+            let changed = lift(game, v, progress, prog_sort, nr_of_steps);
+            *nr_of_subiterations += 1;
+            if changed {
+                any_change = true;
+            } else {
+                break;
+            }
+        }
+    }
+
+    any_change
+}
+
+/// Sweeps over the vertices until no measure increases, where every sweep counts as an iteration.
+#[allow(clippy::too_many_arguments)]
+fn sweep_until_stable(game: &Game,
+                      vertices: &[u32],
+                      progress: &mut Progress,
+                      prog_sort: ProgSort,
+                      cancel: &Cancellation,
+                      nr_of_iterations: &mut u64,
+                      nr_of_subiterations: &mut u64,
+                      nr_of_steps: &mut u64) -> Result<(), SpmTimedOut> {
+    loop {
+        *nr_of_iterations += 1;
+        let any_change = sweep(game, vertices, progress, prog_sort, cancel, nr_of_subiterations, nr_of_steps);
+
+        if cancel.is_cancelled() {
+            return Err(SpmTimedOut {
+                nr_of_iterations: *nr_of_iterations,
+                nr_of_subiterations: *nr_of_subiterations,
+                nr_of_steps: *nr_of_steps
+            });
+        }
+        if !any_change {
+            return Ok(());
+        }
+    }
+}
+
+/// Computes the small progress measures of the player, where the nodes with measure `Top` are won by its opponent.
+///
+/// The token is checked before every node is lifted. Once it is cancelled, the statistics so far are returned instead.
+pub fn small_progress_measures(game: &Game,
+                               strategy: &dyn Strategy,
+                               prog_sort: ProgSort,
+                               player: Owner,
+                               cancel: &Cancellation) -> Result<SpmResult, SpmTimedOut> {
+    let mut progress = game.new_progress(player);
     let vertices = strategy.vertex();
     let mut nr_of_iterations = 0;
     let mut nr_of_subiterations = 0;
+    let mut nr_of_steps = 0;
+
+    sweep_until_stable(game,
+                       &vertices,
+                       &mut progress,
+                       prog_sort,
+                       cancel,
+                       &mut nr_of_iterations,
+                       &mut nr_of_subiterations,
+                       &mut nr_of_steps)?;
+
+    Ok(spm_result(game, progress, nr_of_iterations, nr_of_subiterations, nr_of_steps))
+}
+
+/// Computes the small progress measures of the player using a worklist instead of repeated sweeps over all nodes.
+///
+/// Initially every node is on the worklist, in the order of the strategy. After the measure of a node increased,
+/// only its predecessors can be lifted further, so only those are put back on the worklist. The token is checked before
+/// every node is lifted, as in `small_progress_measures`.
+pub fn small_progress_measures_worklist(game: &Game,
+                                        strategy: &dyn Strategy,
+                                        prog_sort: ProgSort,
+                                        player: Owner,
+                                        cancel: &Cancellation) -> Result<SpmResult, SpmTimedOut> {
+    small_progress_measures_from(game, game.new_progress(player), strategy.vertex(), prog_sort, cancel)
+}
+
+/// Continues lifting the progress measure from the nodes on the worklist, as in `small_progress_measures_worklist`.
+///
+/// Every measure must be at most the least fixpoint, and every node of which the measure is not stable must be on the
+/// worklist. The least fixpoint is then reached from the measures, which is how solutions are reused after an edit.
+pub fn small_progress_measures_from(game: &Game,
+                                    mut progress: Progress,
+                                    worklist: Vec<u32>,
+                                    prog_sort: ProgSort,
+                                    cancel: &Cancellation) -> Result<SpmResult, SpmTimedOut> {
+    let mut on_worklist = vec![false; game.len()];
+    for &v in &worklist {
+        on_worklist[v as usize] = true;
+    }
+    let mut worklist = worklist.into_iter().collect::<VecDeque<u32>>();
+    let mut nr_of_iterations = 0;
+    let mut nr_of_subiterations = 0;
+    let mut nr_of_steps = 0;
+
+    while let Some(v) = worklist.pop_front() {
+        if cancel.is_cancelled() {
+            return Err(SpmTimedOut { nr_of_iterations, nr_of_subiterations, nr_of_steps });
+        }
+
+        on_worklist[v as usize] = false;
+        nr_of_subiterations += 1;
+
+        if !lift(game, v, &mut progress, prog_sort, &mut nr_of_steps) {
+            continue;
+        }
 
-    loop {
         nr_of_iterations += 1;
 
-        let mut any_change = false;
-
-        for v in &vertices {
-            loop {
-                let progress_new = lift(game, v, &progress);
-                nr_of_subiterations += 1;
-                if progress != progress_new {
-                    progress = progress_new;
-                    any_change = true;
-                } else {
-                    break;
-                }
+        for &u in game.pred(v) {
+            if !on_worklist[u as usize] && progress.measure(u) != &MeasureT::Top {
+                on_worklist[u as usize] = true;
+                worklist.push_back(u);
             }
         }
+    }
 
-        if !any_change {
-            break;
+    Ok(spm_result(game, progress, nr_of_iterations, nr_of_subiterations, nr_of_steps))
+}
+
+/// The largest number of nodes that a thread of `small_progress_measures_parallel` takes from the worklist at once.
+const BATCH_SIZE: usize = 64;
+
+/// Computes the small progress measures of the player with several threads that share a worklist.
+///
+/// Every thread repeatedly takes a batch of nodes from the worklist, which no other thread takes at the same time,
+/// and lifts them as in `small_progress_measures_worklist`. The threads share one table of measures, in which every
+/// measure is replaced as a whole and only by a greater one. As `prog` is monotone, every measure in the table stays
+/// below the least fixpoint, and a node is put back on the worklist whenever a successor increased after it was taken.
+/// Once the worklist is empty and no thread is lifting, the table is therefore the unique least fixpoint, no matter in
+/// which order the threads lifted the nodes. The iterations and sub-iterations are counted over all threads, and the
+/// token is checked before every node is lifted.
+pub fn small_progress_measures_parallel(game: &Game,
+                                        strategy: &dyn Strategy,
+                                        prog_sort: ProgSort,
+                                        player: Owner,
+                                        threads: usize,
+                                        cancel: &Cancellation) -> Result<SpmResult, SpmTimedOut> {
+    let measures = game.nodes().map(|_| RwLock::new(game.new_measure())).collect::<Vec<_>>();
+    let worklist = Mutex::new(strategy.vertex().into_iter().collect::<VecDeque<u32>>());
+    let on_worklist = game.nodes().map(|_| AtomicBool::new(true)).collect::<Vec<_>>();
+    // The number of threads lifting a batch, which may still put nodes on the worklist.
+    let busy = AtomicUsize::new(0);
+    let nr_of_iterations = AtomicU64::new(0);
+    let nr_of_subiterations = AtomicU64::new(0);
+    let nr_of_steps = AtomicU64::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !cancel.is_cancelled() {
+                    let batch = {
+                        let mut worklist = worklist.lock().unwrap();
+                        if worklist.is_empty() && busy.load(Ordering::SeqCst) == 0 {
+                            return;
+                        }
+
+                        let share = cmp::max(1, worklist.len() / threads);
+                        let size = cmp::min(worklist.len(), cmp::min(BATCH_SIZE, share));
+                        if size > 0 {
+                            busy.fetch_add(1, Ordering::SeqCst);
+                        }
+                        worklist.drain(..size).collect::<Vec<u32>>()
+                    };
+
+                    if batch.is_empty() {
+                        // The threads that are still lifting may put nodes back on the worklist.
+                        thread::yield_now();
+                        continue;
+                    }
+
+                    for v in batch {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+
+                        on_worklist[v as usize].store(false, Ordering::SeqCst);
+                        nr_of_subiterations.fetch_add(1, Ordering::Relaxed);
+
+                        let mut steps = 0;
+                        let lifted = lift_shared(game, v, player, &measures, prog_sort, &mut steps);
+                        nr_of_steps.fetch_add(steps, Ordering::Relaxed);
+                        if !lifted {
+                            continue;
+                        }
+
+                        nr_of_iterations.fetch_add(1, Ordering::Relaxed);
+
+                        let mut worklist = worklist.lock().unwrap();
+                        for &u in game.pred(v) {
+                            let top = *measures[u as usize].read().unwrap() == MeasureT::Top;
+                            if !top && !on_worklist[u as usize].swap(true, Ordering::SeqCst) {
+                                worklist.push_back(u);
+                            }
+                        }
+                    }
+
+                    busy.fetch_sub(1, Ordering::SeqCst);
+                }
+            });
         }
+    });
+
+    let nr_of_iterations = nr_of_iterations.into_inner();
+    let nr_of_subiterations = nr_of_subiterations.into_inner();
+    let nr_of_steps = nr_of_steps.into_inner();
+
+    if cancel.is_cancelled() {
+        return Err(SpmTimedOut { nr_of_iterations, nr_of_subiterations, nr_of_steps });
     }
 
-    // println!("Number of iterations: {}", nr_of_iterations);
-    // println!("Number of sub-iterations: {}", nr_of_subiterations);
-    let mut l_global_iterations = nr_of_subiterations;
-    unsafe {
-        l_global_iterations = global_iterations.clone();
-        // println!("Number of global-iterations: {}", global_iterations);
+    let progress = Progress {
+        player,
+        measures: measures.into_iter().map(|m| m.into_inner().unwrap()).collect()
+    };
+
+    Ok(spm_result(game, progress, nr_of_iterations, nr_of_subiterations, nr_of_steps))
+}
+
+/// Computes the small progress measures of both players at the same time, sweeping over the nodes for each in turn.
+///
+/// A node with measure `Top` for one player is won by the other, so the nodes are solved once either progress
+/// measure is stable, or once every node has measure `Top` for either player. The least fixpoint of each measure is
+/// `Top` on the nodes won by the opponent, so those are set to `Top`, and a measure that is not stable yet is lifted
+/// further on the nodes its player wins only, after which both yield a strategy. Every sweep counts as an iteration.
+/// The token is checked before every node is lifted, as in `small_progress_measures`.
+pub fn small_progress_measures_two_sided(game: &Game,
+                                         strategy: &dyn Strategy,
+                                         prog_sort: ProgSort,
+                                         cancel: &Cancellation) -> Result<SpmResult, SpmTimedOut> {
+    let mut progress_even = game.new_progress(Owner::Even);
+    let mut progress_odd = game.new_progress(Owner::Odd);
+    let vertices = strategy.vertex();
+    let mut nr_of_iterations = 0;
+    let mut nr_of_subiterations = 0;
+    let mut nr_of_steps = 0;
+
+    // The measure that is stable, if any, of which the nodes with measure `Top` are exactly those won by the opponent.
+    let stable = loop {
+        nr_of_iterations += 1;
+        let any_change = sweep(game,
+                               &vertices,
+                               &mut progress_even,
+                               prog_sort,
+                               cancel,
+                               &mut nr_of_subiterations,
+                               &mut nr_of_steps);
+        if cancel.is_cancelled() {
+            return Err(SpmTimedOut { nr_of_iterations, nr_of_subiterations, nr_of_steps });
+        }
+        if !any_change {
+            break Some(Owner::Even);
+        }
+
+        nr_of_iterations += 1;
+        let any_change = sweep(game,
+                               &vertices,
+                               &mut progress_odd,
+                               prog_sort,
+                               cancel,
+                               &mut nr_of_subiterations,
+                               &mut nr_of_steps);
+        if cancel.is_cancelled() {
+            return Err(SpmTimedOut { nr_of_iterations, nr_of_subiterations, nr_of_steps });
+        }
+        if !any_change {
+            break Some(Owner::Odd);
+        }
+
+        let decided = game
+            .nodes()
+            .all(|v| progress_even.measure(v) == &MeasureT::Top || progress_odd.measure(v) == &MeasureT::Top);
+
+        if decided {
+            break None;
+        }
+    };
+
+    let (won_even, won_odd) = match stable {
+        Some(Owner::Even) => (progress_even.winning_set(Owner::Even), progress_even.winning_set(Owner::Odd)),
+        _                 => (progress_odd.winning_set(Owner::Even), progress_odd.winning_set(Owner::Odd)),
+    };
+
+    for v in game.nodes() {
+        if won_even.contains(&v) {
+            progress_odd.measures[v as usize] = MeasureT::Top;
+        } else {
+            progress_even.measures[v as usize] = MeasureT::Top;
+        }
     }
 
-    return SpmResult {
-        prog: progress,
-        nr_of_iterations: nr_of_iterations,
-        nr_of_subiterations: nr_of_subiterations,
-        global_iterations: l_global_iterations
+    if stable != Some(Owner::Even) {
+        let vertices_even = vertices.iter().cloned().filter(|v| won_even.contains(v)).collect::<Vec<u32>>();
+        sweep_until_stable(game,
+                           &vertices_even,
+                           &mut progress_even,
+                           prog_sort,
+                           cancel,
+                           &mut nr_of_iterations,
+                           &mut nr_of_subiterations,
+                           &mut nr_of_steps)?;
     }
-}
\ No newline at end of file
+    if stable != Some(Owner::Odd) {
+        let vertices_odd = vertices.iter().cloned().filter(|v| won_odd.contains(v)).collect::<Vec<u32>>();
+        sweep_until_stable(game,
+                           &vertices_odd,
+                           &mut progress_odd,
+                           prog_sort,
+                           cancel,
+                           &mut nr_of_iterations,
+                           &mut nr_of_subiterations,
+                           &mut nr_of_steps)?;
+    }
+    let strategy_even = measure_strategy(game, &progress_even);
+    let strategy_odd = measure_strategy(game, &progress_odd);
+
+    Ok(SpmResult {
+        won_even,
+        won_odd,
+        progress_even: Some(progress_even),
+        progress_odd: Some(progress_odd),
+        strategy_even,
+        strategy_odd,
+        nr_of_iterations,
+        nr_of_subiterations,
+        nr_of_steps
+    })
+}
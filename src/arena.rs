@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::hash::Hash;
+use std::iter;
+use std::slice;
+
+use pg::*;
+
+/// A game of which the nodes are only known through their successors, so it can be explored on the fly.
+///
+/// Unlike `Game`, an arena does not need to know all its nodes or the predecessors of a node, which allows user code
+/// to generate nodes lazily from a model. The solvers that work on arenas only explore the nodes they need.
+pub trait Arena {
+    /// The type by which the arena identifies a node.
+    type Node: Copy + Eq + Hash;
+    type Successors<'a>: Iterator<Item = Self::Node> where Self: 'a;
+
+    fn prio(&self, v: Self::Node) -> u32;
+    fn owner(&self, v: Self::Node) -> Owner;
+    /// Returns the successors of the node, of which there must be at least one.
+    fn successors(&self, v: Self::Node) -> Self::Successors<'_>;
+    /// Returns the node from which a play starts, or `None` if the arena has no nodes.
+    fn initial(&self) -> Option<Self::Node>;
+}
+
+/// The nodes of a game are identified by their index. A game starts in the node with identifier 0 if there is one,
+/// otherwise in the first node. An empty game has no initial node.
+impl Arena for Game {
+    type Node = u32;
+    type Successors<'a> = iter::Cloned<slice::Iter<'a, u32>>;
+
+    fn prio(&self, v: u32) -> u32 {
+        Game::prio(self, v)
+    }
+
+    fn owner(&self, v: u32) -> Owner {
+        Game::owner(self, v)
+    }
+
+    fn successors(&self, v: u32) -> Self::Successors<'_> {
+        self.succ(v).iter().cloned()
+    }
+
+    fn initial(&self) -> Option<u32> {
+        self.index(0).or_else(|| self.nodes().next())
+    }
+}
+
+/// Computes the attractor of the owner to the target set within the subgame induced by the provided nodes.
+///
+/// An arena does not know the predecessors of its nodes, so these are given by `pred`, which must return at least the
+/// predecessors of a node within the subgame. A node of the owner is attracted if it has a successor in the attractor,
+/// a node of the opponent is attracted if all its successors within the subgame are in the attractor. The target set
+/// is expected to be part of the subgame. Every edge of the subgame is visited at most once.
+pub fn attractor<A, P, I>(arena: &A,
+                          owner: Owner,
+                          subgame: &HashSet<A::Node>,
+                          target: &HashSet<A::Node>,
+                          pred: P) -> Attractor<A::Node>
+    where A: Arena, P: Fn(A::Node) -> I, I: IntoIterator<Item = A::Node> {
+    let mut nodes = target.clone();
+    let mut strategy = PositionalStrategy::new();
+    let mut queue = target.iter().cloned().collect::<VecDeque<A::Node>>();
+
+    // The number of successors of an opponent node that have not been attracted yet.
+    let mut remaining: HashMap<A::Node, usize> = HashMap::new();
+
+    while let Some(w) = queue.pop_front() {
+        for v in pred(w) {
+            if !subgame.contains(&v) || nodes.contains(&v) {
+                continue;
+            }
+
+            if arena.owner(v) == owner {
+                strategy.0.insert(v, w);
+            } else {
+                let count = remaining
+                    .entry(v)
+                    .or_insert_with(|| arena.successors(v).filter(|x| subgame.contains(x)).count());
+                *count -= 1;
+
+                if *count > 0 {
+                    continue;
+                }
+            }
+
+            nodes.insert(v);
+            queue.push_back(v);
+        }
+    }
+
+    Attractor {
+        nodes,
+        strategy
+    }
+}
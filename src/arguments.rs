@@ -2,36 +2,58 @@ use std::env;
 use std::ffi::OsStr;
 use std::path::Path;
 use std::process;
-use std::fmt;
 
+use solver;
+use strategies;
 
+
+/// The rule used by strategy improvement to switch to better successors.
 #[derive(Debug, Copy, Clone)]
-pub enum StrategySort {
-    Random,
-    Input,
-    Priority,
-    Succesor,
-    SelfLoop
+pub enum ImprovementSort {
+    AllSwitches,
+    SingleSwitch,
+    RandomFacet
 }
 
-impl fmt::Display for StrategySort {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-        // write!(f, "{}", match self {
-        //     Random => "Random",
-        //     Input => "Input",
-        //     Priority => "Priority",
-        //     Succesor => "Succesor",
-        //     SelfLoop => "SelfLoop"
-        // })
-    }
+/// The player whose progress measures are computed by small progress measures.
+#[derive(Debug, Copy, Clone)]
+pub enum MeasureSort {
+    Even,
+    Odd,
+    TwoSided
+}
+
+/// What to do with a game in which validation finds problems.
+#[derive(Debug, Copy, Clone)]
+pub enum ValidationSort {
+    Reject,
+    Repair
 }
 
 #[derive(Debug, Clone)]
 pub struct Arguments {
     pub testing: bool,
     pub pg_file: String,
-    pub strategy: Option<StrategySort>
+    /// The name of the solver, see `solver::NAMES`.
+    pub solver: String,
+    /// The name of the strategy in which the progress measure solvers lift the nodes, see `strategies::NAMES`.
+    pub strategy: Option<String>,
+    pub improvement: Option<ImprovementSort>,
+    pub measure: Option<MeasureSort>,
+    pub worklist: bool,
+    /// The number of threads of the progress measure solvers and Zielonka's algorithm.
+    pub threads: usize,
+    pub decompose: bool,
+    pub preprocess: bool,
+    pub incremental: bool,
+    pub justify: bool,
+    pub verify: bool,
+    /// The identifier of the node of which only the winner is determined, see `local::solve_local`.
+    pub query: Option<u32>,
+    /// The file of edits after each of which the game is solved again, see `resolving::ResolvingSolver`.
+    pub edits_file: Option<String>,
+    pub solution_file: Option<String>,
+    pub validation: Option<ValidationSort>
 }
 
 fn exit_and_print_usage(args: &Vec<String>) {
@@ -40,8 +62,17 @@ fn exit_and_print_usage(args: &Vec<String>) {
         .and_then(OsStr::to_str)
         .unwrap_or("<application name>");
 
-    println!("usage: {} -pg <file path> [-input]/[-random]/[-priority]/[-selfloop]/[-succesor]", prog_name);
-    println!("or: {} [-ex <directory path>]", prog_name);
+    println!("usage: {} -pg <file path> [-spm]/[-succinct] [-input]/[-random]/[-priority]/[-selfloop]/[-succesor] [-even]/[-odd]/[-twosided] [-worklist] [-threads <n>] [-incremental] [-preprocess] [-scc] [-verify] [-o <file path>] [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -zielonka [-threads <n>] [-preprocess] [-scc] [-verify] [-o <file path>] [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -pp/-tl [-preprocess] [-scc] [-verify] [-o <file path>] [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -fpi [-justify] [-preprocess] [-scc] [-verify] [-o <file path>] [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -si [-allswitches]/[-singleswitch]/[-randomfacet] [-preprocess] [-scc] [-verify] [-o <file path>] [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -solver <name> [-strategy <name>] [options]", prog_name);
+    println!("or: {} -pg <file path> -query <node id> [-reject]/[-repair]", prog_name);
+    println!("or: {} -pg <file path> -edits <file path> [-even]/[-odd] [-incremental] [-verify] [-reject]/[-repair]", prog_name);
+    println!("or: {} [-ex <directory path>] [-incremental]", prog_name);
+    println!("solvers: {}", solver::NAMES.join(", "));
+    println!("strategies: {}", strategies::NAMES.join(", "));
     process::exit(0);
 }
 
@@ -53,6 +84,7 @@ pub fn get() -> Arguments {
     }
 
     let mut pg_file = None;
+    let mut solver = None;
     let mut strategy = None;
 
     let mut args_iter = args.iter();
@@ -60,6 +92,19 @@ pub fn get() -> Arguments {
     // Skip the first argument as this is the program name.
     args_iter.next();
     let mut testing = false;
+    let mut worklist = false;
+    let mut threads = 1;
+    let mut decompose = false;
+    let mut preprocess = false;
+    let mut incremental = false;
+    let mut justify = false;
+    let mut verify = false;
+    let mut query = None;
+    let mut edits_file = None;
+    let mut solution_file = None;
+    let mut validation = None;
+    let mut improvement = None;
+    let mut measure = None;
 
     loop {
         let arg = args_iter.next();
@@ -85,40 +130,209 @@ pub fn get() -> Arguments {
                     exit_and_print_usage(&args);
                 }
             },
+            "-spm" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("spm");
+            },
+            "-zielonka" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("zielonka");
+            },
+            "-pp" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("pp");
+            },
+            "-si" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("si");
+            },
+            "-tl" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("tl");
+            },
+            "-succinct" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("succinct");
+            },
+            "-fpi" => {
+                if solver.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                solver = Some("fpi");
+            },
+            "-solver" => {
+                match args_iter.next() {
+                    Some(name) if solver.is_none() && solver::NAMES.contains(&name.as_str()) => {
+                        solver = Some(name.as_str());
+                    },
+                    _ => exit_and_print_usage(&args),
+                }
+            },
+            "-strategy" => {
+                match args_iter.next() {
+                    Some(name) if strategy.is_none() && strategies::NAMES.contains(&name.as_str()) => {
+                        strategy = Some(name.as_str());
+                    },
+                    _ => exit_and_print_usage(&args),
+                }
+            },
+            "-allswitches" => {
+                if improvement.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                improvement = Some(ImprovementSort::AllSwitches);
+            },
+            "-singleswitch" => {
+                if improvement.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                improvement = Some(ImprovementSort::SingleSwitch);
+            },
+            "-randomfacet" => {
+                if improvement.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                improvement = Some(ImprovementSort::RandomFacet);
+            },
+            "-even" => {
+                if measure.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                measure = Some(MeasureSort::Even);
+            },
+            "-odd" => {
+                if measure.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                measure = Some(MeasureSort::Odd);
+            },
+            "-twosided" => {
+                if measure.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                measure = Some(MeasureSort::TwoSided);
+            },
+            "-query" => {
+                match args_iter.next().and_then(|id| id.parse::<u32>().ok()) {
+                    Some(id) if query.is_none() => {
+                        query = Some(id);
+                    },
+                    _ => exit_and_print_usage(&args),
+                }
+            },
+            "-edits" => {
+                edits_file = args_iter.next();
+
+                if edits_file.is_none() {
+                    exit_and_print_usage(&args);
+                }
+            },
+            "-o" => {
+                solution_file = args_iter.next();
+
+                if solution_file.is_none() {
+                    exit_and_print_usage(&args);
+                }
+            },
+            "-reject" => {
+                if validation.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                validation = Some(ValidationSort::Reject);
+            },
+            "-repair" => {
+                if validation.is_some() {
+                    exit_and_print_usage(&args);
+                }
+
+                validation = Some(ValidationSort::Repair);
+            },
+            "-worklist" => {
+                worklist = true;
+            },
+            "-threads" => {
+                match args_iter.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) if n > 0 => {
+                        threads = n;
+                    },
+                    _ => exit_and_print_usage(&args),
+                }
+            },
+            "-scc" => {
+                decompose = true;
+            },
+            "-preprocess" => {
+                preprocess = true;
+            },
+            "-incremental" => {
+                incremental = true;
+            },
+            "-justify" => {
+                justify = true;
+            },
+            "-verify" => {
+                verify = true;
+            },
             "-input" => {
                 if strategy.is_some() {
                     exit_and_print_usage(&args);
                 }
 
-                strategy = Some(StrategySort::Input);
+                strategy = Some("input");
             }
             "-random" => {
                 if strategy.is_some() {
                     exit_and_print_usage(&args);
                 }
 
-                strategy = Some(StrategySort::Random);
+                strategy = Some("random");
             },
             "-priority" => {
                 if strategy.is_some() {
                     exit_and_print_usage(&args);
                 }
 
-                strategy = Some(StrategySort::Priority);
+                strategy = Some("priority");
             },
             "-succesor" => {
                 if strategy.is_some() {
                     exit_and_print_usage(&args);
                 }
 
-                strategy = Some(StrategySort::Succesor);
+                strategy = Some("succesor");
             },
             "-selfloop" => {
                 if strategy.is_some() {
                     exit_and_print_usage(&args);
                 }
 
-                strategy = Some(StrategySort::SelfLoop);
+                strategy = Some("selfloop");
             },
             x => {
                 println!("Unknown: {}", x);
@@ -127,13 +341,41 @@ pub fn get() -> Arguments {
         };
     }
 
-    if pg_file.is_none() || (strategy.is_none() && !testing) {
+    let solver = solver.unwrap_or("spm");
+    let needs_strategy = !testing && query.is_none() && edits_file.is_none() && solver::needs_strategy(solver);
+
+    if pg_file.is_none() || (strategy.is_none() && needs_strategy) || (testing && query.is_some()) {
+        exit_and_print_usage(&args);
+    }
+
+    // The edits are solved again by the progress measures of a single player, and not in combination with other modes.
+    if edits_file.is_some() && (testing || query.is_some() || matches!(measure, Some(MeasureSort::TwoSided))) {
+        exit_and_print_usage(&args);
+    }
+
+    // The two-sided variant sweeps over the nodes for both players in turn, which does not combine with a worklist or
+    // with several threads.
+    if let (Some(MeasureSort::TwoSided), true) = (measure, worklist || threads > 1) {
         exit_and_print_usage(&args);
     }
 
     Arguments {
         pg_file: pg_file.unwrap().clone(),
         testing: testing,
-        strategy: strategy
+        solver: solver.to_string(),
+        strategy: strategy.map(|s| s.to_string()),
+        improvement,
+        measure,
+        worklist,
+        threads,
+        decompose,
+        preprocess,
+        incremental,
+        justify,
+        verify,
+        query,
+        edits_file: edits_file.cloned(),
+        solution_file: solution_file.cloned(),
+        validation
     }
 }
\ No newline at end of file
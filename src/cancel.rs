@@ -0,0 +1,37 @@
+use std::time::{Duration, Instant};
+
+/// A token by which a solver is asked to stop once a deadline has passed.
+///
+/// Solvers check the token regularly and stop with the statistics they gathered so far once it is cancelled. A token
+/// can be shared by the solvers in several threads, which are then all cancelled at the same deadline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cancellation {
+    deadline: Option<Instant>
+}
+
+impl Cancellation {
+    /// Creates a token that is never cancelled.
+    pub fn new() -> Cancellation {
+        Cancellation {
+            deadline: None
+        }
+    }
+
+    /// Creates a token that is cancelled once the timeout has passed from now.
+    pub fn with_timeout(timeout: Duration) -> Cancellation {
+        Cancellation {
+            deadline: Some(Instant::now() + timeout)
+        }
+    }
+
+    /// Returns `true` if the deadline of the token has passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+impl Default for Cancellation {
+    fn default() -> Cancellation {
+        Cancellation::new()
+    }
+}
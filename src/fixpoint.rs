@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+
+/// Which distractions are reset when new distractions are found at a more significant priority.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ResetSort {
+    /// Reset every distraction with a less significant priority, as in distraction fixpoint iteration.
+    All,
+    /// Only reset the distractions with a less significant priority that are no longer justified, and only evaluate
+    /// the nodes that are not justified, as in fixpoint iteration with justifications by Lapauw et al.
+    Justified
+}
+
+pub struct FixpointResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// The number of times new distractions were found, each of which starts a new iteration.
+    pub nr_of_iterations: u64,
+    /// The number of distractions that were reset.
+    pub nr_of_resets: u64
+}
+
+/// The statistics of fixpoint iteration that was cancelled before it solved the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FixpointTimedOut {
+    pub nr_of_iterations: u64,
+    pub nr_of_resets: u64
+}
+
+/// Returns the player that currently wins the node.
+///
+/// A node is won by the player of its priority, unless it is a distraction.
+fn winner(game: &Game, distractions: &[bool], v: u32) -> Owner {
+    let player = Owner::of_prio(game.prio(v));
+
+    if distractions[v as usize] {
+        player.opponent()
+    } else {
+        player
+    }
+}
+
+/// Returns the player that wins the node in one step, together with the successors that justify this.
+///
+/// The owner wins if it can move to a node it currently wins, which is then the justification. Otherwise the opponent
+/// wins, justified by all successors.
+fn one_step(game: &Game, distractions: &[bool], v: u32) -> (Owner, Vec<u32>) {
+    let owner = game.owner(v);
+
+    match game.succ(v).iter().find(|w| winner(game, distractions, **w) == owner) {
+        Some(w) => (owner, vec![*w]),
+        None    => (owner.opponent(), game.succ(v).to_vec()),
+    }
+}
+
+/// Resets the nodes whose justification depends on the distractions found at `p`, and returns the number of
+/// distractions that were reset.
+///
+/// A node depends on the found distractions if it is justified by one of them, or by a node with a priority less
+/// significant than `p` that depends on them. Such a node with a less significant priority is no longer a distraction,
+/// and no node that depends on the found distractions remains justified, so they are all evaluated again.
+fn reset_dependent(game: &Game,
+                   distractions: &mut [bool],
+                   justification: &mut [Option<Vec<u32>>],
+                   found: &[u32],
+                   p: u32)
+                   -> u64 {
+    let mut justifies = vec![Vec::new(); game.len()];
+    for v in game.nodes() {
+        for w in justification[v as usize].iter().flatten() {
+            justifies[*w as usize].push(v);
+        }
+    }
+
+    let mut nr_of_resets = 0;
+    let mut visited = vec![false; game.len()];
+    let mut stack = found.to_vec();
+    for v in found {
+        visited[*v as usize] = true;
+    }
+
+    while let Some(w) = stack.pop() {
+        for &v in &justifies[w as usize] {
+            if visited[v as usize] {
+                continue;
+            }
+
+            visited[v as usize] = true;
+            justification[v as usize] = None;
+
+            if game.prio(v) > p {
+                if distractions[v as usize] {
+                    distractions[v as usize] = false;
+                    nr_of_resets += 1;
+                }
+                stack.push(v);
+            }
+        }
+    }
+
+    nr_of_resets
+}
+
+/// Returns the strategy of the player, which moves from every node it owns and wins to the successor that justifies
+/// this.
+fn justified_strategy(game: &Game,
+                      distractions: &[bool],
+                      justification: &[Option<Vec<u32>>],
+                      player: Owner)
+                      -> PositionalStrategy {
+    let mut strategy = PositionalStrategy::new();
+
+    for v in game.nodes() {
+        if game.owner(v) == player && winner(game, distractions, v) == player {
+            let just = justification[v as usize].as_ref().expect("Every node is justified at the end.");
+            strategy.0.insert(v, just[0]);
+        }
+    }
+
+    strategy
+}
+
+/// The ranks of the nodes at every level of the iteration, from which the strategies are obtained.
+///
+/// A round of a level ends every time distractions are found at it, which also ends the current rounds of all less
+/// significant levels. At the end of a round, a node is ranked with the number of the round if the priority of the
+/// level is bad for the player that currently wins the node, unless it was ranked at the level since the level was
+/// last reset. Moving to a successor with the least ranks at the priorities that are at least as significant as that
+/// of the node then makes progress on every bad priority, as with progress measures.
+struct Ranks {
+    /// The number of rounds of every level that ended since the level was last reset.
+    rounds: Vec<u64>,
+    /// The ranks of every node at every level, indexed by the index of the node.
+    ranks: Vec<Vec<u64>>,
+    /// The most significant level at which every node was ranked since that level was last reset.
+    recorded: Vec<Option<usize>>
+}
+
+impl Ranks {
+    fn new(game: &Game, nr_of_levels: usize) -> Ranks {
+        Ranks {
+            rounds: vec![0; nr_of_levels],
+            ranks: vec![vec![0; nr_of_levels]; game.len()],
+            recorded: vec![None; game.len()]
+        }
+    }
+
+    /// Ranks the nodes at the levels before `end`, of which `prios` are the priorities.
+    fn record(&mut self, game: &Game, distractions: &[bool], prios: &[u32], end: usize) {
+        for v in game.nodes() {
+            let from = self.recorded[v as usize].map_or(0, |j| j + 1);
+            let won = winner(game, distractions, v);
+
+            for (j, p) in prios.iter().enumerate().take(end).skip(from) {
+                if won != Owner::of_prio(*p) {
+                    self.ranks[v as usize][j] = self.rounds[j];
+                    self.recorded[v as usize] = Some(j);
+                }
+            }
+        }
+    }
+
+    /// Ends the current round of level `i` before the distractions found at it are added, and resets the less
+    /// significant levels.
+    fn end_round(&mut self, game: &Game, distractions: &[bool], prios: &[u32], i: usize) {
+        self.record(game, distractions, prios, i + 1);
+        self.rounds[i] += 1;
+
+        for j in 0..i {
+            self.rounds[j] = 0;
+        }
+
+        for recorded in &mut self.recorded {
+            if recorded.is_some_and(|j| j < i) {
+                *recorded = None;
+            }
+        }
+    }
+
+    /// Returns the ranks of `w` at the levels that are bad for the player, from the most significant level down to the
+    /// priority of the node `v` that moves to `w`.
+    fn progress(&self, game: &Game, prios: &[u32], player: Owner, v: u32, w: u32) -> Vec<u64> {
+        (0..prios.len())
+            .rev()
+            .filter(|j| Owner::of_prio(prios[*j]) != player && prios[*j] <= game.prio(v))
+            .map(|j| self.ranks[w as usize][j])
+            .collect()
+    }
+
+    /// Returns the strategy of the player, which moves from every node it owns and wins to a successor it wins with the
+    /// least ranks.
+    fn strategy(&self, game: &Game, distractions: &[bool], prios: &[u32], player: Owner) -> PositionalStrategy {
+        let mut strategy = PositionalStrategy::new();
+
+        for v in game.nodes() {
+            if game.owner(v) != player || winner(game, distractions, v) != player {
+                continue;
+            }
+
+            let w = game.succ(v)
+                .iter()
+                .filter(|w| winner(game, distractions, **w) == player)
+                .min_by_key(|w| self.progress(game, prios, player, v, **w))
+                .unwrap();
+            strategy.0.insert(v, *w);
+        }
+
+        strategy
+    }
+}
+
+/// Solves the game using fixpoint iteration with distractions, as by van Dijk and Rubbens.
+///
+/// Every node is assumed to be won by the player of its priority. Starting at the least significant (highest)
+/// priority, the nodes of a priority that are won by the opponent in one step become distractions. Then the
+/// distractions with less significant priorities are reset, and the iteration starts over. The nodes are solved if no
+/// new distractions are found at any priority.
+///
+/// For `ResetSort::All` the strategies move to the successor with the least ranks, see `Ranks`. For
+/// `ResetSort::Justified` they move to the successor that justifies the node, as the justifications of the nodes won
+/// by their owners never form a cycle that is losing for them.
+///
+/// The token is checked before the nodes of every priority are evaluated. Once it is cancelled, the statistics so far
+/// are returned instead.
+pub fn fixpoint_iteration(game: &Game,
+                          reset_sort: ResetSort,
+                          cancel: &Cancellation) -> Result<FixpointResult, FixpointTimedOut> {
+    let mut prios = game.nodes().map(|v| game.prio(v)).collect::<Vec<u32>>();
+    prios.sort_by(|a, b| b.cmp(a));
+    prios.dedup();
+    let levels = prios
+        .iter()
+        .map(|p| game.nodes().filter(|v| game.prio(*v) == *p).collect::<Vec<u32>>())
+        .collect::<Vec<_>>();
+
+    let mut distractions = vec![false; game.len()];
+    let mut ranks = match reset_sort {
+        ResetSort::All       => Some(Ranks::new(game, prios.len())),
+        ResetSort::Justified => None,
+    };
+    let mut justification: Vec<Option<Vec<u32>>> = vec![None; game.len()];
+    let mut nr_of_iterations = 0;
+    let mut nr_of_resets = 0;
+    let mut i = 0;
+
+    while i < prios.len() {
+        if cancel.is_cancelled() {
+            return Err(FixpointTimedOut { nr_of_iterations, nr_of_resets });
+        }
+
+        let p = prios[i];
+        let mut found = Vec::new();
+
+        for &v in &levels[i] {
+            let skip = match reset_sort {
+                ResetSort::All       => distractions[v as usize],
+                ResetSort::Justified => justification[v as usize].is_some(),
+            };
+            if skip {
+                continue;
+            }
+
+            let (won, just) = one_step(game, &distractions, v);
+            justification[v as usize] = Some(just);
+
+            if !distractions[v as usize] && won != Owner::of_prio(p) {
+                found.push(v);
+            }
+        }
+
+        if found.is_empty() {
+            i += 1;
+            continue;
+        }
+
+        nr_of_iterations += 1;
+
+        if let Some(ref mut ranks) = ranks {
+            ranks.end_round(game, &distractions, &prios, i);
+        }
+
+        for &v in &found {
+            distractions[v as usize] = true;
+        }
+
+        match reset_sort {
+            ResetSort::All       => {
+                for v in game.nodes() {
+                    if distractions[v as usize] && game.prio(v) > p {
+                        distractions[v as usize] = false;
+                        nr_of_resets += 1;
+                    }
+                }
+            },
+            ResetSort::Justified => {
+                nr_of_resets += reset_dependent(game, &mut distractions, &mut justification, &found, p);
+            },
+        }
+
+        i = 0;
+    }
+
+    let (won_even, won_odd): (HashSet<u32>, HashSet<u32>) = game
+        .nodes()
+        .partition(|v| winner(game, &distractions, *v) == Owner::Even);
+    let (strategy_even, strategy_odd) = match ranks {
+        Some(mut ranks) => {
+            ranks.record(game, &distractions, &prios, prios.len());
+
+            (ranks.strategy(game, &distractions, &prios, Owner::Even),
+             ranks.strategy(game, &distractions, &prios, Owner::Odd))
+        },
+        None            => (justified_strategy(game, &distractions, &justification, Owner::Even),
+                            justified_strategy(game, &distractions, &justification, Owner::Odd)),
+    };
+
+    Ok(FixpointResult {
+        won_even,
+        won_odd,
+        strategy_even,
+        strategy_odd,
+        nr_of_iterations,
+        nr_of_resets
+    })
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use pg::*;
+use arena;
+use arena::Arena;
+use zielonka;
+
+/// The number of nodes that is explored before the first attempt to decide the query node.
+const INITIAL_BUDGET: usize = 64;
+
+pub struct LocalResult<N: Eq + Hash = u32> {
+    /// The player that wins from the query node.
+    pub winner: Owner,
+    /// Nodes won by the winner, including the query node, on which the strategy of the winner stays.
+    pub won: HashSet<N>,
+    /// The winning strategy of the winner on the nodes it won.
+    pub strategy: PositionalStrategy<N>,
+    pub nr_of_explored_nodes: u64,
+    /// The number of times the explored part of the game was solved.
+    pub nr_of_attempts: u64
+}
+
+/// The part of an arena that has been explored, which is all that is known of it.
+struct Explored<N: Eq + Hash> {
+    nodes: HashSet<N>,
+    /// Explored nodes of which the successors have not been explored yet.
+    open: Vec<N>,
+    /// The predecessors of every explored node among the nodes that are not open.
+    pred: HashMap<N, Vec<N>>
+}
+
+impl<N: Copy + Eq + Hash> Explored<N> {
+    /// Explores the nodes reachable from the open nodes until the budget is reached or nothing is left to explore.
+    fn explore<A: Arena<Node = N>>(&mut self, arena: &A, budget: usize) {
+        let mut i = 0;
+
+        while i < self.open.len() && self.nodes.len() < budget {
+            let v = self.open[i];
+
+            for w in arena.successors(v) {
+                self.pred.entry(w).or_default().push(v);
+
+                if self.nodes.insert(w) {
+                    self.open.push(w);
+                }
+            }
+
+            i += 1;
+        }
+
+        self.open.drain(..i);
+    }
+}
+
+/// Returns the nodes of the explored part of the arena that the player wins, whatever happens outside of that part.
+///
+/// The open nodes are assumed to be lost by the player, so its opponent attracts to them. In the remainder every node
+/// of the player has a successor outside of the attractor, and every node of the opponent has all its successors in
+/// the remainder, as they are not open. The opponent cannot leave the remainder, so what the player wins there is
+/// won in the entire arena. The remainder is turned into a `Game`, which is solved by Zielonka's algorithm.
+fn won_regardless<A: Arena>(arena: &A,
+                            player: Owner,
+                            explored: &Explored<A::Node>) -> (HashSet<A::Node>, PositionalStrategy<A::Node>) {
+    let open = explored.open.iter().cloned().collect::<HashSet<A::Node>>();
+    let pred = |v| explored.pred.get(&v).into_iter().flatten().cloned();
+    let a = arena::attractor(arena, player.opponent(), &explored.nodes, &open, pred);
+    let rest = explored.nodes.difference(&a.nodes).cloned().collect::<Vec<A::Node>>();
+
+    let game = Game::from_arena(arena, &rest);
+    let result = zielonka::zielonka(&game);
+    let (won, strategy) = match player {
+        Owner::Even => (result.won_even, result.strategy_even),
+        Owner::Odd  => (result.won_odd, result.strategy_odd),
+    };
+
+    // The node at index `i` of the game is the node at position `i` of the remainder.
+    let won = won.iter().map(|v| rest[*v as usize]).collect();
+    let strategy = PositionalStrategy(strategy.0.iter().map(|(v, w)| (rest[*v as usize], rest[*w as usize])).collect());
+
+    (won, strategy)
+}
+
+/// Determines the winner of the node, exploring only the part of the arena it can reach.
+///
+/// The arena is explored breadth-first from the node, doubling the number of explored nodes after every attempt. An
+/// attempt solves the explored part for each player, assuming that the nodes that are not fully explored are lost,
+/// and stops as soon as either player wins the node. Once all reachable nodes are explored, an attempt always decides
+/// the node, so the part of the arena that the node can reach must be finite.
+pub fn solve_local<A: Arena>(arena: &A, v: A::Node) -> LocalResult<A::Node> {
+    let mut explored = Explored {
+        nodes: HashSet::new(),
+        open: vec![v],
+        pred: HashMap::new()
+    };
+    let mut budget = INITIAL_BUDGET;
+    let mut nr_of_attempts = 0;
+    explored.nodes.insert(v);
+
+    loop {
+        explored.explore(arena, budget);
+        nr_of_attempts += 1;
+
+        for player in &[Owner::Even, Owner::Odd] {
+            let (won, strategy) = won_regardless(arena, *player, &explored);
+
+            if won.contains(&v) {
+                return LocalResult {
+                    winner: *player,
+                    won,
+                    strategy,
+                    nr_of_explored_nodes: explored.nodes.len() as u64,
+                    nr_of_attempts
+                };
+            }
+        }
+
+        assert!(!explored.open.is_empty(), "The fully explored arena does not decide the node.");
+        budget *= 2;
+    }
+}
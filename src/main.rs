@@ -3,27 +3,44 @@ mod pg;
 mod parser;
 mod algorithm;
 mod strategies;
+mod verify;
+mod writer;
+mod zielonka;
+mod priority_promotion;
+mod strategy_improvement;
+mod tangle_learning;
+mod succinct;
+mod fixpoint;
+mod scc;
+mod preprocess;
+mod solver;
+mod cancel;
+mod local;
+mod arena;
+mod resolving;
+
+#[cfg(test)]
+mod tests;
 
 use arguments::*;
 use pg::*;
-use strategies::*;
+use arena::Arena;
+use std::collections::HashSet;
 use std::thread;
 use std::fs;
-use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::cmp::Ordering;
 use std::path::PathBuf;
+use std::process;
 use std::usize;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
-use std::os::unix::thread::JoinHandleExt;
+use std::time::Duration;
 
 
 extern crate regex;
 use self::regex::Regex;
 
 use std::cmp;
-extern crate libc;
 
 
 
@@ -68,83 +85,289 @@ fn parse<'exp>(x: PathBuf) -> ExperimentFile {
 fn main() {
     println!("");
     let args = arguments::get();
+    let prog_sort = if args.incremental {
+        algorithm::ProgSort::Incremental
+    } else {
+        algorithm::ProgSort::ClosedForm
+    };
     if !args.testing {
-        let game = parser::parse_from_file(&args.pg_file);
-        println!("");
-        println!("Maximal measure: {:?}", game.max_measure());
-    
-        match args.strategy.unwrap() {
-            StrategySort::Random => run(&game, &RandomStrategy::new(&game)),
-            StrategySort::Input => run(&game, &InputStrategy::new(&game)),
-            StrategySort::Priority => run(&game, &PriorityStrategy::new(&game)),
-            StrategySort::Succesor => run(&game, &SuccesorStrategy::new(&game)),
-            StrategySort::SelfLoop => run(&game, &SelfLoopStrategy::new(&game))
+        let game = match args.validation {
+            None    => parser::parse_from_file(&args.pg_file),
+            Some(_) => parser::parse_lenient_from_file(&args.pg_file),
         };
+        let mut game = match game {
+            Ok(game) => game,
+            Err(err) => {
+                println!("Failed to parse '{}'. {}", args.pg_file, err);
+                process::exit(1);
+            }
+        };
+
+        if let Some(validation_sort) = args.validation {
+            let validation = game.validate();
+
+            if !validation.is_valid() {
+                print!("{}", validation);
+
+                match validation_sort {
+                    ValidationSort::Reject => {
+                        println!("Rejected the game '{}'.", args.pg_file);
+                        process::exit(1);
+                    },
+                    ValidationSort::Repair => {
+                        game = game.repair();
+                        println!("Repaired the game '{}'.", args.pg_file);
+                    }
+                }
+            }
+        }
+        println!("");
+
+        if let Some(node_id) = args.query {
+            print_local(&game, node_id);
+            return;
+        }
+
+        if let Some(ref edits_file) = args.edits_file {
+            let player = match args.measure.unwrap_or(MeasureSort::Even) {
+                MeasureSort::Odd => Owner::Odd,
+                _                => Owner::Even,
+            };
+            print_resolving(game, edits_file, player, prog_sort, args.verify);
+            return;
+        }
+
+        let solver = solver::by_name(&args.solver, &options(&args, prog_sort))
+            .expect("The arguments only accept solvers and strategies that exist.");
+        let solution = wrap(solver, (args.decompose, args.preprocess)).solve(&game);
+        for (name, value) in &solution.statistics {
+            println!("{}: {}", name, value);
+        }
+        let solver::Solution { won_even, won_odd, strategy_even, strategy_odd, .. } = solution;
+
+        print_winning_sets(&game, &won_even, &won_odd);
+        print_strategies(&game, &strategy_even, &strategy_odd);
+
+        if args.verify {
+            print_verification(&game, &won_even, &won_odd, &strategy_even, &strategy_odd);
+        }
+
+        if let Some(ref solution_file) = args.solution_file {
+            writer::write_to_file(solution_file, &game, &won_odd, &strategy_even, &strategy_odd);
+        }
     } else {
         let dir_name = args.pg_file.clone();
         let paths = fs::read_dir(dir_name).unwrap();
-        let mut strategies = vec!(StrategySort::Random, StrategySort::Input, StrategySort::Priority, StrategySort::Succesor, StrategySort::SelfLoop);
+        // Solvers that do not lift nodes in the order of a strategy only need to run once.
+        let mut strategies = if solver::needs_strategy(&args.solver) {
+            strategies::NAMES.to_vec()
+        } else {
+            vec!["input"]
+        };
     
         let l = paths.collect::<Vec<_>>().into_iter().map(|x| x.unwrap().path());
         let mut l: Vec<ExperimentFile> = l.into_iter().map(|x| parse(x)).collect();
         l.sort_by(|a, b| compare(&a, &b));
         for path in l {
-            let (tx, rx) = mpsc::channel();
             // let path = path;//.unwrap().path().clone();
             println!("Name: {}", path.file_name);
             let data = Arc::new(path);
+            // Every strategy gets two minutes, after which its solver stops at the next check of the token.
+            let cancel = cancel::Cancellation::with_timeout(Duration::from_millis(1000 * 2 * 60));
             let mut threads = Vec::new();
             for strat in strategies.clone() {
-                let (data, tx) = (data.clone(), tx.clone());
+                let data = data.clone();
+                let name = args.solver.clone();
+                // Only the winner of the initial node is reported, so the strategies are not needed.
+                let options = solver::Options {
+                    strategy: strat.to_string(),
+                    strategies: false,
+                    ..options(&args, prog_sort)
+                };
+                let wrapped = (args.decompose, args.preprocess);
                 threads.push(thread::spawn(move || {
                     let file = data;
-                    let game = parser::parse_from_file(&file.path);
-                    let p = match strat {
-                        StrategySort::Random => run(&game, &RandomStrategy::new(&game)),
-                        StrategySort::Input => run(&game, &InputStrategy::new(&game)),
-                        StrategySort::Priority => run(&game, &PriorityStrategy::new(&game)),
-                        StrategySort::Succesor => run(&game, &SuccesorStrategy::new(&game)),
-                        StrategySort::SelfLoop => run(&game, &SelfLoopStrategy::new(&game))
+                    let game = match parser::parse_from_file(&file.path) {
+                        Ok(game) => game,
+                        Err(err) => {
+                            println!("Failed to parse '{}'. {}", file.file_name, err);
+                            return (strat, false);
+                        }
                     };
                     // file_name
                     // strategy
-                    // node 0
+                    // node 0, empty or timeout
                     // iterations
-                    let c = p.prog.winning_set(Owner::Odd).contains(&0);
-                    println!("{},{},{},{}", file.file_name, strat, if c { "odd" } else { "even" }, p.nr_of_iterations);
-                    tx.send(strat).unwrap();
+                    let solver = solver::by_name(&name, &options)
+                        .expect("The arguments only accept solvers and strategies that exist.");
+                    let solver = wrap(solver, wrapped);
+                    let (node_0, statistics) = match solver.solve_cancellable(&game, &cancel) {
+                        Ok(p) => {
+                            let winner = match game.initial() {
+                                Some(v) if p.won_odd.contains(&v) => "odd",
+                                Some(_)                           => "even",
+                                None                              => "empty",
+                            };
+                            (winner, p.statistics)
+                        },
+                        Err(timed_out) => ("timeout", timed_out.statistics),
+                    };
+                    let nr_of_iterations = statistics.get("Number of iterations").cloned().unwrap_or(0);
+                    println!("{},{},{},{}", file.file_name, strat, node_0, nr_of_iterations);
+                    (strat, node_0 == "timeout")
                 }));
             }
-            let start = Instant::now();
-            let timeout = Duration::from_millis(1000 * 2 * 60);
-            let mut surviving_strategies = vec!();
-            for _ in strategies.clone() {
-                let diff = Instant::now()+ Duration::from_millis(10) - start;
-                if timeout + Duration::from_millis(100) > diff {
-                    match rx.recv_timeout(timeout + Duration::from_millis(100) - diff) {
-                        Ok(s) => { surviving_strategies.push(s); }
-                        _ => {}
-                    }
-                }
-                // match x {
-                    
-                // }
-            }
-            strategies = surviving_strategies;
-            for t in threads {
-                let pt = t.into_pthread_t();
-                unsafe {
-                    libc::kill(pt as i32, 15);
-                }
-            }
+            // Every solver stops once the token is cancelled, so every thread is joined. A strategy that timed out, or
+            // of which the thread panicked, is not run on the remaining games.
+            strategies = threads
+                .into_iter()
+                .filter_map(|t| t.join().ok())
+                .filter(|(_, timed_out)| !timed_out)
+                .map(|(strat, _)| strat)
+                .collect();
+        }
+    }
+}
+
+/// Returns the options of the solvers chosen by the arguments.
+fn options(args: &Arguments, prog_sort: algorithm::ProgSort) -> solver::Options {
+    solver::Options {
+        strategy: args.strategy.clone().unwrap_or_else(|| "input".to_string()),
+        prog_sort,
+        player: match args.measure.unwrap_or(MeasureSort::Even) {
+            MeasureSort::Even     => Some(Owner::Even),
+            MeasureSort::Odd      => Some(Owner::Odd),
+            MeasureSort::TwoSided => None,
+        },
+        worklist: args.worklist,
+        threads: args.threads,
+        rule: match args.improvement.unwrap_or(ImprovementSort::AllSwitches) {
+            ImprovementSort::AllSwitches  => strategy_improvement::ImprovementRule::AllSwitches,
+            ImprovementSort::SingleSwitch => strategy_improvement::ImprovementRule::SingleSwitch,
+            ImprovementSort::RandomFacet  => strategy_improvement::ImprovementRule::RandomFacet,
+        },
+        reset_sort: if args.justify {
+            fixpoint::ResetSort::Justified
+        } else {
+            fixpoint::ResetSort::All
+        },
+        strategies: true
+    }
+}
+
+/// Wraps the solver to solve one strongly connected component at a time and to preprocess the game, if requested.
+fn wrap(solver: Box<dyn solver::Solver>, (decompose, preprocess): (bool, bool)) -> Box<dyn solver::Solver> {
+    let solver = if decompose {
+        Box::new(solver::Decomposition { inner: solver })
+    } else {
+        solver
+    };
+
+    if preprocess {
+        Box::new(solver::Preprocessing { inner: solver })
+    } else {
+        solver
+    }
+}
+
+fn print_winning_sets(game: &Game, won_even: &HashSet<u32>, won_odd: &HashSet<u32>) {
+    let mut won_even = won_even.iter().map(|v| game.id(*v)).collect::<Vec<_>>();
+    let mut won_odd = won_odd.iter().map(|v| game.id(*v)).collect::<Vec<_>>();
+    won_even.sort();
+    won_odd.sort();
+
+    println!();
+    println!("Won even: {:?}", won_even);
+    println!();
+    println!("Won odd : {:?}", won_odd);
+}
+
+fn print_strategies(game: &Game, strategy_even: &PositionalStrategy, strategy_odd: &PositionalStrategy) {
+    let mut strategy_even = strategy_even.0.iter().map(|(v, w)| (game.id(*v), game.id(*w))).collect::<Vec<_>>();
+    let mut strategy_odd = strategy_odd.0.iter().map(|(v, w)| (game.id(*v), game.id(*w))).collect::<Vec<_>>();
+    strategy_even.sort();
+    strategy_odd.sort();
+
+    println!();
+    println!("Strategy even: {:?}", strategy_even);
+    println!();
+    println!("Strategy odd : {:?}", strategy_odd);
+}
+
+fn print_local(game: &Game, node_id: u32) {
+    let result = match game.index(node_id) {
+        Some(v) => local::solve_local(game, v),
+        None => {
+            println!("There is no node with identifier {}.", node_id);
+            process::exit(1);
         }
+    };
+
+    let mut won = result.won.iter().map(|v| game.id(*v)).collect::<Vec<_>>();
+    let mut strategy = result.strategy.0.iter().map(|(v, w)| (game.id(*v), game.id(*w))).collect::<Vec<_>>();
+    won.sort();
+    strategy.sort();
+
+    println!("Number of explored nodes: {}", result.nr_of_explored_nodes);
+    println!("Number of attempts: {}", result.nr_of_attempts);
+    println!();
+    println!("Node {} is won by {:?}.", node_id, result.winner);
+    println!();
+    println!("Won by {:?}: {:?}", result.winner, won);
+    println!();
+    println!("Strategy of {:?}: {:?}", result.winner, strategy);
+}
+
+/// Solves the game, and solves it again after every edit in the file, reusing the measures of the previous solution.
+fn print_resolving(game: Game, edits_file: &str, player: Owner, prog_sort: algorithm::ProgSort, verify: bool) {
+    let edits = match parser::parse_edits_from_file(&game, edits_file) {
+        Ok(edits) => edits,
+        Err(err) => {
+            println!("Failed to parse '{}'. {}", edits_file, err);
+            process::exit(1);
+        }
+    };
+
+    let mut solver = resolving::ResolvingSolver::new(game, player, prog_sort);
+    let mut result = solver.solve();
+    println!("Initial solution: {} iterations, {} sub-iterations",
+             result.nr_of_iterations, result.nr_of_subiterations);
+
+    for (i, edit) in edits.into_iter().enumerate() {
+        if !solver.apply(edit) {
+            println!("Edit {}: no change", i + 1);
+            continue;
+        }
+
+        result = solver.solve();
+        println!("Edit {}: {} reset nodes, {} iterations, {} sub-iterations",
+                 i + 1, result.nr_of_reset_nodes, result.nr_of_iterations, result.nr_of_subiterations);
+    }
+
+    // Only the strategy of the player follows from its measure, the strategy of its opponent is only needed once.
+    let game = solver.game();
+    match player {
+        Owner::Even => result.strategy_odd = algorithm::subgame_strategy(game, Owner::Odd, &result.won_odd),
+        Owner::Odd  => result.strategy_even = algorithm::subgame_strategy(game, Owner::Even, &result.won_even),
+    }
+
+    print_winning_sets(game, &result.won_even, &result.won_odd);
+    print_strategies(game, &result.strategy_even, &result.strategy_odd);
+
+    if verify {
+        print_verification(game, &result.won_even, &result.won_odd, &result.strategy_even, &result.strategy_odd);
     }
 }
 
-fn run(game: &Game, strat: &Strategy) -> algorithm::SpmResult {
-    algorithm::small_progress_measures(&game, strat)
-    // println!("");
-    // println!("Won even: {:?}", progress.winning_set(Owner::Even));
-    // println!("");
-    // println!("Won odd : {:?}", progress.winning_set(Owner::Odd));
-}
\ No newline at end of file
+fn print_verification(game: &Game,
+                      won_even: &HashSet<u32>,
+                      won_odd: &HashSet<u32>,
+                      strategy_even: &PositionalStrategy,
+                      strategy_odd: &PositionalStrategy) {
+    println!();
+
+    match verify::verify(game, won_even, won_odd, strategy_even, strategy_odd) {
+        Ok(())   => println!("Verification: the solution is correct."),
+        Err(err) => println!("Verification failed: {}", err),
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
@@ -7,136 +8,354 @@ use std::io::prelude::*;
 use pg::Game;
 use pg::Node;
 use pg::Owner;
+use resolving::Edit;
+
+/// The reason a header or node specification could not be parsed.
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /// The header does not contain a max identifier, or it is not a natural number.
+    InvalidHeader,
+    /// The node specification does not contain an identifier.
+    MissingId,
+    /// The identifier is not a natural number.
+    InvalidId,
+    /// The node specification does not contain a priority.
+    MissingPriority,
+    /// The priority is not a natural number.
+    InvalidPriority,
+    /// The node specification does not contain an owner.
+    MissingOwner,
+    /// The owner is not in the range [0-1].
+    BadOwner,
+    /// The node specification does not contain any successors.
+    MissingSuccessors,
+    /// The successor is not a natural number.
+    InvalidSuccessor(String),
+    /// The successor is not the identifier of a node in the game.
+    UnknownSuccessor(u32),
+    /// The name is not enclosed in quotes.
+    InvalidName,
+    /// A node with the identifier was already specified.
+    DuplicateId(u32),
+    /// The identifier is larger than the max identifier specified in the header.
+    HeaderMismatch { max_id: u32, id: u32 },
+    /// The edit specification is not of the form '<kind> <identifier> <value>'.
+    InvalidEdit,
+    /// The game does not contain a node with the identifier.
+    UnknownId(u32)
+}
+
+/// An error that occurred while parsing a parity game or edits to it.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// The line on which the offending header or node specification starts, starting at 1.
+    pub line: usize,
+    /// The offending header, node specification or edit specification.
+    pub spec: String,
+    pub kind: ParseErrorKind
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            ParseErrorKind::InvalidHeader                  => "The header does not contain a valid max identifier.".to_string(),
+            ParseErrorKind::MissingId                      => "No ID defined.".to_string(),
+            ParseErrorKind::InvalidId                      => "The ID is not a natural number.".to_string(),
+            ParseErrorKind::MissingPriority                => "No prio defined.".to_string(),
+            ParseErrorKind::InvalidPriority                => "The prio is not a natural number.".to_string(),
+            ParseErrorKind::MissingOwner                   => "No owner defined.".to_string(),
+            ParseErrorKind::BadOwner                       => "The owner is invalid. Must be in range [0-1].".to_string(),
+            ParseErrorKind::MissingSuccessors              => "No successors defined.".to_string(),
+            ParseErrorKind::InvalidSuccessor(ref s)        => format!("Invalid successor '{}'.", s),
+            ParseErrorKind::UnknownSuccessor(id)           => format!("The successor {} is not a node of the game.", id),
+            ParseErrorKind::InvalidName                    => "No name is defined, but is expected to.".to_string(),
+            ParseErrorKind::DuplicateId(id)                => format!("The ID {} is defined more than once.", id),
+            ParseErrorKind::HeaderMismatch { max_id, id }  => format!("The ID {} exceeds the max identifier {} of the header.", id, max_id),
+            ParseErrorKind::InvalidEdit                    => "The edit is not one of 'add', 'remove', 'prio' or 'owner' followed by two natural numbers.".to_string(),
+            ParseErrorKind::UnknownId(id)                  => format!("The game has no node with ID {}.", id),
+        };
+
+        write!(f, "Line {}: {} In: '{}'", self.line, reason, self.spec.trim())
+    }
+}
 
 /// Tries to parse the header data from the provided string.
 ///
 /// If the provided string is a valid header, then the maximum node ID specified in this header is returned.
-/// This value is then wrapped in `Some`. Otherwise, if the string is not a valid header, `None` is returned.
+/// This value is then wrapped in `Some`. Otherwise, if the string is not a header, `None` is returned.
 /// A valid header has the format: 'parity <identifier>'.
 ///
-/// #Panics
-/// If the provided string is a header, but is in an invalid format.
-fn try_parse_header(header: &str) -> Option<u32> {
+/// Returns the kind of error if the provided string is a header, but is in an invalid format.
+fn try_parse_header(header: &str) -> Result<Option<u32>, ParseErrorKind> {
     let mut split = header.split_whitespace();
 
-    let def = split.next();
-    if def.is_some() && def.unwrap() == "parity" {
-        let max_id = split
-            .next()
-            .expect(&format!("The header '{}' does not contain a max identifier.", header))
-            .parse::<u32>()
-            .expect(&format!("The max identifier of the header '{}' is not a valid natural number.", header));
-        
-        Some(max_id)
-    } else {
-        None
+    if split.next() != Some("parity") {
+        return Ok(None);
     }
+
+    split
+        .next()
+        .and_then(|max_id| max_id.parse::<u32>().ok())
+        .map(Some)
+        .ok_or(ParseErrorKind::InvalidHeader)
 }
 
 /// Parse the node specification from the provided string.
 ///
-/// #Panics
-/// If the provided string is not a valid node specification.
 /// A valid node specification has the format: '<identifier> <priority> <owner> <successor> [<name>] [;]'.
-fn parse_node_spec(i: usize, node_spec: &str) -> Node {
+/// Returns the kind of error if the provided string is not a valid node specification.
+fn parse_node_spec(i: usize, node_spec: &str) -> Result<Node, ParseErrorKind> {
     let mut split = node_spec.split_whitespace();
 
     let id = split
         .next()
-        .expect(&format!("No ID defined for node spec: '{}'", node_spec))
+        .ok_or(ParseErrorKind::MissingId)?
         .parse::<u32>()
-        .expect(&format!("The ID defined for node spec: '{}' is not a natural number.", node_spec));
-    
+        .map_err(|_| ParseErrorKind::InvalidId)?;
+
     let prio = split
         .next()
-        .expect(&format!("No prio defined for node spec: '{}'", node_spec))
+        .ok_or(ParseErrorKind::MissingPriority)?
         .parse::<u32>()
-        .expect(&format!("The prio defined for node spec '{}' is not a natural number.", node_spec));
+        .map_err(|_| ParseErrorKind::InvalidPriority)?;
 
     let owner = split
         .next()
-        .expect(&format!("No owner defined for node spec: '{}'", node_spec))
+        .ok_or(ParseErrorKind::MissingOwner)?
         .parse::<Owner>()
-        .expect(&format!("The owner defined for node spec '{}' is invalid. Must be in rage [0-1].", node_spec));
-    
+        .map_err(|_| ParseErrorKind::BadOwner)?;
+
     let succ = split
         .next()
-        .expect(&format!("No successors defined for node spec: '{}'", node_spec))
+        .ok_or(ParseErrorKind::MissingSuccessors)?
         .split(',')
-        .map(|s| s.parse::<u32>().expect(&format!("Invalid successor '{}' in node spec: '{}'.", s, node_spec)))
-        .collect::<HashSet<u32>>();
+        .map(|s| s.parse::<u32>().map_err(|_| ParseErrorKind::InvalidSuccessor(s.to_string())))
+        .collect::<Result<HashSet<u32>, ParseErrorKind>>()?;
 
-    let name = split
-        .next()
-        .map(|n| { n
+    let name = match split.next() {
+        Some(n) => Some(n
             .split('"')
-            .filter(|part| !part.is_empty())
-            .next()
-            .expect(&format!("No name is defined, but is expected to in the string: '{}' of node spec: '{}'.", n, node_spec))
-            .to_string()
-        });
+            .find(|part| !part.is_empty())
+            .ok_or(ParseErrorKind::InvalidName)?
+            .to_string()),
+        None    => None,
+    };
 
-    Node {
+    Ok(Node {
         id: id,
         count: i,
         prio: prio,
         owner: owner,
         succ: succ,
         name: name
-    }
+    })
 }
 
 /// Parses the provided string as a parity game.
 ///
-/// # Panics
-/// - The string contains multiple headers.
+/// Returns an error if:
 /// - The string contains an invalid header or node specification.
-pub fn parse(parity_game: &str) -> Game {
+/// - The string contains multiple node specifications with the same ID.
+/// - A node ID exceeds the max identifier specified in the header.
+/// - A successor is not the ID of a node in the game.
+pub fn parse(parity_game: &str) -> Result<Game, ParseError> {
+    parse_with(parity_game, false)
+}
+
+/// Parses the provided string as a parity game, tolerating duplicate IDs, a mismatching header and unknown successors.
+///
+/// Of multiple node specifications with the same ID, the last one is kept, and unknown successors are left out. These
+/// problems are remembered in the game, so these can be reported by `Game::validate`.
+///
+/// Returns an error if the string contains an invalid header or node specification.
+pub fn parse_lenient(parity_game: &str) -> Result<Game, ParseError> {
+    parse_with(parity_game, true)
+}
+
+fn parse_with(parity_game: &str, lenient: bool) -> Result<Game, ParseError> {
     let mut nodes = HashMap::new();
-    let mut lines = parity_game
-        .trim()
-        .split(';')
-        .peekable();
+    let mut specs_by_id = HashMap::new();
+    let mut duplicate_ids = Vec::new();
+
+    let mut lines = specs(parity_game).peekable();
 
     // Check if the first line is a header.
-    let max_id = lines
-        .peek()
-        .and_then(|header| try_parse_header(header));
-    
+    let max_id = match lines.peek() {
+        Some(&(line, header)) => try_parse_header(header).map_err(|kind| ParseError {
+            line,
+            spec: header.to_string(),
+            kind
+        })?,
+        None                  => None,
+    };
+
     // If the first line was indeed a header, skip it.
     if max_id.is_some() {
         lines.next();
     }
 
     // Parse the rest of the lines as node specifications.
-    for (i, line) in lines.enumerate() {
+    for (i, (line, spec)) in lines.enumerate() {
         // Ignore this line if it only consists of white spaces or is empty.
-        if line.trim().is_empty() {
+        if spec.trim().is_empty() {
             continue;
         }
 
-        let node = parse_node_spec(i, line);
+        let error = |kind| ParseError {
+            line,
+            spec: spec.to_string(),
+            kind
+        };
+
+        let node = parse_node_spec(i, spec).map_err(error)?;
+
+        if let Some(max_id) = max_id {
+            if node.id > max_id && !lenient {
+                return Err(error(ParseErrorKind::HeaderMismatch { max_id, id: node.id }));
+            }
+        }
+
+        if nodes.contains_key(&node.id) {
+            if !lenient {
+                return Err(error(ParseErrorKind::DuplicateId(node.id)));
+            }
+
+            duplicate_ids.push(node.id);
+        }
+
+        specs_by_id.insert(node.id, (line, spec));
         nodes.insert(node.id, node);
     }
 
-    Game::new(nodes)
+    // An unknown successor is reported at the first node specification that refers to it.
+    if !lenient {
+        let mut ids = nodes.keys().cloned().collect::<Vec<u32>>();
+        ids.sort_by_key(|id| specs_by_id[id].0);
+
+        for id in ids {
+            let mut succ = nodes[&id].succ.iter().cloned().collect::<Vec<u32>>();
+            succ.sort();
+
+            if let Some(w) = succ.into_iter().find(|w| !nodes.contains_key(w)) {
+                let (line, spec) = specs_by_id[&id];
+
+                return Err(ParseError {
+                    line,
+                    spec: spec.to_string(),
+                    kind: ParseErrorKind::UnknownSuccessor(w)
+                });
+            }
+        }
+    }
+
+    Ok(Game::from_parsed(nodes, max_id, duplicate_ids))
+}
+
+/// Splits the string into the specifications separated by ';', each paired with the line on which it starts.
+fn specs(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut line = 1;
+
+    text
+        .split(';')
+        .map(move |spec| {
+            let leading = &spec[..spec.len() - spec.trim_start().len()];
+            let spec_line = line + leading.matches('\n').count();
+            line += spec.matches('\n').count();
+            (spec_line, spec)
+        })
+}
+
+/// Parses the identifier of a node in the game, returning the index of the node.
+fn parse_edit_node(game: &Game, id: Option<&str>) -> Result<u32, ParseErrorKind> {
+    let id = id
+        .and_then(|id| id.parse::<u32>().ok())
+        .ok_or(ParseErrorKind::InvalidEdit)?;
+
+    game.index(id).ok_or(ParseErrorKind::UnknownId(id))
+}
+
+/// Parse the edit specification from the provided string.
+///
+/// A valid edit specification has one of the formats: 'add <identifier> <identifier>', 'remove <identifier>
+/// <identifier>', 'prio <identifier> <priority>' or 'owner <identifier> <owner>', where the identifiers are those of
+/// nodes in the game. Returns the kind of error if the provided string is not a valid edit specification.
+fn parse_edit_spec(game: &Game, edit_spec: &str) -> Result<Edit, ParseErrorKind> {
+    let mut split = edit_spec.split_whitespace();
+
+    let edit = match split.next() {
+        Some("add")    => Edit::AddEdge(parse_edit_node(game, split.next())?, parse_edit_node(game, split.next())?),
+        Some("remove") => Edit::RemoveEdge(parse_edit_node(game, split.next())?, parse_edit_node(game, split.next())?),
+        Some("prio")   => Edit::SetPrio(parse_edit_node(game, split.next())?, split
+            .next()
+            .and_then(|prio| prio.parse::<u32>().ok())
+            .ok_or(ParseErrorKind::InvalidEdit)?),
+        Some("owner")  => Edit::SetOwner(parse_edit_node(game, split.next())?, split
+            .next()
+            .and_then(|owner| owner.parse::<Owner>().ok())
+            .ok_or(ParseErrorKind::InvalidEdit)?),
+        _              => return Err(ParseErrorKind::InvalidEdit),
+    };
+
+    if split.next().is_some() {
+        return Err(ParseErrorKind::InvalidEdit);
+    }
+
+    Ok(edit)
+}
+
+/// Parses the provided string as a sequence of edits to the game, separated by ';'.
+///
+/// Returns an error if the string contains an invalid edit specification, or one that refers to a node that is not
+/// in the game.
+pub fn parse_edits(game: &Game, edits: &str) -> Result<Vec<Edit>, ParseError> {
+    specs(edits)
+        .filter(|&(_, spec)| !spec.trim().is_empty())
+        .map(|(line, spec)| parse_edit_spec(game, spec).map_err(|kind| ParseError {
+            line,
+            spec: spec.to_string(),
+            kind
+        }))
+        .collect()
 }
 
 /// Parses a parity game from the specified file.
 ///
+/// Returns an error if the contents of the file are not a valid parity game, see `parse`.
+///
 /// # Panics
 /// - The file does not exist.
-/// - The string contains multiple headers.
-/// - The string contains an invalid header or node specification.
-pub fn parse_from_file(file_path: &str) -> Game {
+/// - The file cannot be read.
+pub fn parse_from_file(file_path: &str) -> Result<Game, ParseError> {
+    parse(&read_file(file_path))
+}
+
+/// Parses a parity game from the specified file, tolerating duplicate IDs and a mismatching header, see `parse_lenient`.
+///
+/// # Panics
+/// - The file does not exist.
+/// - The file cannot be read.
+pub fn parse_lenient_from_file(file_path: &str) -> Result<Game, ParseError> {
+    parse_lenient(&read_file(file_path))
+}
+
+/// Parses edits to the game from the specified file, see `parse_edits`.
+///
+/// # Panics
+/// - The file does not exist.
+/// - The file cannot be read.
+pub fn parse_edits_from_file(game: &Game, file_path: &str) -> Result<Vec<Edit>, ParseError> {
+    parse_edits(game, &read_file(file_path))
+}
+
+fn read_file(file_path: &str) -> String {
     let file = File::open(file_path)
-        .expect(&format!("Failed to open the file: '{}'.", file_path));
-        
+        .unwrap_or_else(|_| panic!("Failed to open the file: '{}'.", file_path));
+
     let mut buf_reader = BufReader::new(file);
     let mut contents = String::new();
     buf_reader
         .read_to_string(&mut contents)
-        .expect(&format!("Failed to read the file: '{}'.", file_path));
+        .unwrap_or_else(|_| panic!("Failed to read the file: '{}'.", file_path));
 
-    parse(&contents)
-}
\ No newline at end of file
+    contents
+}
@@ -2,18 +2,45 @@ use std::cmp;
 use std::collections::HashSet;
 use std::collections::HashMap;
 use std::collections::LinkedList;
+use std::fmt;
+use std::ops::Range;
 use std::str;
 use std::hash::{Hash, Hasher, SipHasher};
+use std::sync::atomic::{self, AtomicBool, AtomicUsize};
+use std::thread;
 
 use std::cmp::Ordering;
 
+use arena;
+use arena::Arena;
+use scc;
 
-#[derive(Debug, Eq, PartialEq)]
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
 pub enum Owner {
     Even,
     Odd,
 }
 
+impl Owner {
+    /// Returns the player that wins a play in which the provided priority is the lowest priority occurring infinitely often.
+    pub fn of_prio(prio: u32) -> Owner {
+        if prio % 2 == 1 {
+            Owner::Odd
+        } else {
+            Owner::Even
+        }
+    }
+
+    /// Returns the opponent of this player.
+    pub fn opponent(&self) -> Owner {
+        match *self {
+            Owner::Even => Owner::Odd,
+            Owner::Odd  => Owner::Even,
+        }
+    }
+}
+
 impl str::FromStr for Owner {
     type Err = String;
 
@@ -53,34 +80,492 @@ impl Hash for Node {
 }
 
 
+/// A positional strategy, mapping a node to the successor that is chosen in it.
+///
+/// The nodes are the indices of the nodes of a `Game`, unless the strategy is computed on another `Arena`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PositionalStrategy<N: Eq + Hash = u32>(pub HashMap<N, N>);
+
+impl<N: Eq + Hash> PositionalStrategy<N> {
+    pub fn new() -> PositionalStrategy<N> {
+        PositionalStrategy(HashMap::new())
+    }
+
+    /// Returns the successor chosen in the specified node, or `None` if the strategy is not defined on it.
+    pub fn successor(&self, node_id: &N) -> Option<&N> {
+        self.0.get(node_id)
+    }
+}
+
+/// The attractor of a player to a target set.
+#[derive(Debug)]
+pub struct Attractor<N: Eq + Hash = u32> {
+    /// The nodes from which the player can force the play into the target set, including the target set itself.
+    pub nodes: HashSet<N>,
+    /// The choices of the attracting player on its own nodes outside of the target set.
+    pub strategy: PositionalStrategy<N>
+}
+
+/// The problems found by validating a game.
+#[derive(Debug, Default, PartialEq)]
+pub struct Validation {
+    /// The edges to successors that are not nodes of the game.
+    pub dangling_edges: Vec<(u32, u32)>,
+    /// The nodes without any successors, including those of which all successors are dangling.
+    pub dead_ends: Vec<u32>,
+    /// The identifiers that were specified more than once, of which only the last specification was kept.
+    pub duplicate_ids: Vec<u32>,
+    /// The max identifier declared in the header and the actual max identifier, if these disagree.
+    pub header_mismatch: Option<(u32, u32)>
+}
+
+impl Validation {
+    /// Returns `true` if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self == &Validation::default()
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(v, w) in &self.dangling_edges {
+            writeln!(f, "The node {} has the successor {}, which does not exist.", v, w)?;
+        }
+        for v in &self.dead_ends {
+            writeln!(f, "The node {} has no successors.", v)?;
+        }
+        for v in &self.duplicate_ids {
+            writeln!(f, "The node {} is defined more than once, only the last definition is kept.", v)?;
+        }
+        if let Some((declared, actual)) = self.header_mismatch {
+            writeln!(f, "The header declares the max identifier {}, but the actual max identifier is {}.", declared, actual)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A parity game in a dense, array-based representation.
+///
+/// The nodes are numbered by contiguous indices in the order in which they were specified. The successors and
+/// predecessors of all nodes are stored in flat arrays, in which the edges of node `v` are found in the range
+/// `offsets[v]..offsets[v + 1]`. All solvers refer to nodes by their index, `id` and `index` convert between the
+/// index of a node and the identifier it was specified with.
 #[derive(Debug)]
 pub struct Game {
-    id_to_node: HashMap<u32, Node>,
+    ids: Vec<u32>,
+    id_to_index: HashMap<u32, u32>,
+    prio: Vec<u32>,
+    owner: Vec<Owner>,
+    name: Vec<Option<String>>,
+    succ_offsets: Vec<usize>,
+    succ: Vec<u32>,
+    pred_offsets: Vec<usize>,
+    pred: Vec<u32>,
     max_prio: u32,
-    max_measure: Measure
+    max_measure_even: Measure,
+    max_measure_odd: Measure,
+    header: Option<u32>,
+    duplicate_ids: Vec<u32>,
+    dangling_edges: Vec<(u32, u32)>
 }
 
 impl Game {
 
-    pub fn new(id_to_node: HashMap<u32, Node>) -> Game {
-        let max_prio = Game::_max_prio(&id_to_node);
-        let max_measure = Game::_max_measure(&id_to_node, max_prio);
+    /// Creates a game from parsed nodes, remembering the max identifier declared in the header and the identifiers
+    /// that were specified more than once, so these can be reported by `validate`.
+    ///
+    /// Successors that are not nodes of the game cannot be represented, they are left out and reported by `validate`.
+    /// Only `parser::parse_lenient` produces such successors, `parser::parse` rejects them.
+    pub fn from_parsed(id_to_node: HashMap<u32, Node>, header: Option<u32>, duplicate_ids: Vec<u32>) -> Game {
+        let mut nodes = id_to_node.into_iter().map(|(_, n)| n).collect::<Vec<Node>>();
+        nodes.sort_by_key(|n| (n.count, n.id));
+
+        let id_to_index = nodes
+            .iter()
+            .enumerate()
+            .map(|(v, n)| (n.id, v as u32))
+            .collect::<HashMap<u32, u32>>();
+
+        let mut dangling_edges = Vec::new();
+        let mut succ = Vec::with_capacity(nodes.len());
+
+        for node in &nodes {
+            let mut succ_v = Vec::with_capacity(node.succ.len());
+
+            for w in &node.succ {
+                match id_to_index.get(w) {
+                    Some(w) => succ_v.push(*w),
+                    None    => dangling_edges.push((node.id, *w)),
+                }
+            }
+
+            succ_v.sort();
+            succ.push(succ_v);
+        }
+
+        dangling_edges.sort();
+
+        let ids = nodes.iter().map(|n| n.id).collect();
+        let prio = nodes.iter().map(|n| n.prio).collect();
+        let owner = nodes.iter().map(|n| n.owner).collect();
+        let name = nodes.into_iter().map(|n| n.name).collect();
+
+        Game::build(ids, id_to_index, prio, owner, name, succ, header, duplicate_ids, dangling_edges)
+    }
+
+    /// Builds the flat successor and predecessor arrays from the successors of every node.
+    #[allow(clippy::too_many_arguments)]
+    fn build(ids: Vec<u32>,
+             id_to_index: HashMap<u32, u32>,
+             prio: Vec<u32>,
+             owner: Vec<Owner>,
+             name: Vec<Option<String>>,
+             succ: Vec<Vec<u32>>,
+             header: Option<u32>,
+             duplicate_ids: Vec<u32>,
+             dangling_edges: Vec<(u32, u32)>) -> Game {
+        let (succ_offsets, succ_flat) = Game::_flatten(&succ);
+        let (pred_offsets, pred_flat) = Game::_flatten(&Game::_pred(&succ));
+
+        let max_prio = Game::_max_prio(&prio);
+        let max_measure_even = Game::_max_measure(&prio, max_prio, Owner::Even);
+        let max_measure_odd = Game::_max_measure(&prio, max_prio, Owner::Odd);
 
         Game {
-            id_to_node: id_to_node,
-            max_prio: max_prio,
-            max_measure: max_measure
+            ids,
+            id_to_index,
+            prio,
+            owner,
+            name,
+            succ_offsets,
+            succ: succ_flat,
+            pred_offsets,
+            pred: pred_flat,
+            max_prio,
+            max_measure_even,
+            max_measure_odd,
+            header,
+            duplicate_ids,
+            dangling_edges
+        }
+    }
+
+    /// Checks the game for dangling edges, dead ends, duplicate identifiers and a header that disagrees with the nodes.
+    ///
+    /// The solvers assume that none of these are present.
+    pub fn validate(&self) -> Validation {
+        let mut dead_ends = self
+            .nodes()
+            .filter(|v| self.succ(*v).is_empty())
+            .map(|v| self.id(v))
+            .collect::<Vec<u32>>();
+        dead_ends.sort();
+
+        let max_id = self.ids.iter().max().cloned().unwrap_or(0);
+        let header_mismatch = self.header
+            .filter(|header| *header != max_id)
+            .map(|header| (header, max_id));
+
+        Validation {
+            dangling_edges: self.dangling_edges.clone(),
+            dead_ends,
+            duplicate_ids: self.duplicate_ids.clone(),
+            header_mismatch
+        }
+    }
+
+    /// Repairs the problems that `validate` reports.
+    ///
+    /// Dangling edges were already left out. Every dead end, including those that only lost their successors by
+    /// leaving out dangling edges, gets a self-loop that is losing for its owner. If the priority of the dead end is of
+    /// the parity of its owner it is increased by one, which only affects the plays that end in the dead end.
+    /// Duplicate identifiers and the header are forgotten, as nothing remains to repair about them.
+    pub fn repair(self) -> Game {
+        let mut prio = self.prio.clone();
+        let mut succ = Vec::with_capacity(self.ids.len());
+
+        for v in self.nodes() {
+            let mut succ_v = self.succ(v).to_vec();
+
+            if succ_v.is_empty() {
+                succ_v.push(v);
+
+                if Owner::of_prio(prio[v as usize]) == self.owner(v) {
+                    prio[v as usize] += 1;
+                }
+            }
+
+            succ.push(succ_v);
+        }
+
+        Game::build(self.ids, self.id_to_index, prio, self.owner, self.name, succ, None, Vec::new(), Vec::new())
+    }
+
+    /// Returns the number of nodes in the game.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if the game has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the indices of all nodes in the game.
+    pub fn nodes(&self) -> Range<u32> {
+        0..self.ids.len() as u32
+    }
+
+    /// Returns the identifier the node with the specified index was specified with.
+    pub fn id(&self, v: u32) -> u32 {
+        self.ids[v as usize]
+    }
+
+    /// Returns the index of the node with the specified identifier, or `None` if there is no such node.
+    pub fn index(&self, id: u32) -> Option<u32> {
+        self.id_to_index.get(&id).cloned()
+    }
+
+    pub fn prio(&self, v: u32) -> u32 {
+        self.prio[v as usize]
+    }
+
+    pub fn owner(&self, v: u32) -> Owner {
+        self.owner[v as usize]
+    }
+
+    /// Returns the indices of the successors of the node, in ascending order.
+    pub fn succ(&self, v: u32) -> &[u32] {
+        &self.succ[self.succ_offsets[v as usize]..self.succ_offsets[v as usize + 1]]
+    }
+
+    /// Returns the indices of the nodes that have the specified node as a successor, in ascending order.
+    pub fn pred(&self, v: u32) -> &[u32] {
+        &self.pred[self.pred_offsets[v as usize]..self.pred_offsets[v as usize + 1]]
+    }
+
+    /// Computes the attractor of the owner to the target set within the subgame induced by the provided nodes.
+    ///
+    /// A node of the owner is attracted if it has a successor in the attractor, a node of the opponent is attracted
+    /// if all its successors within the subgame are in the attractor. The target set is expected to be part of the subgame.
+    /// Every edge of the subgame is visited at most once.
+    pub fn attractor(&self, owner: Owner, subgame: &HashSet<u32>, target: &HashSet<u32>) -> Attractor {
+        arena::attractor(self, owner, subgame, target, |v| self.pred(v).iter().cloned())
+    }
+
+    /// Computes the same attractor as `attractor` with several threads.
+    ///
+    /// The attractor grows one layer at a time: the nodes attracted in the previous layer are divided among the
+    /// threads, which attract the predecessors of their nodes. Every node is attracted by exactly one thread, which for
+    /// a node of the owner chooses the successor from which it was reached.
+    pub fn attractor_parallel(&self,
+                              owner: Owner,
+                              subgame: &HashSet<u32>,
+                              target: &HashSet<u32>,
+                              threads: usize) -> Attractor {
+        // The least number of nodes of a layer that is worth handing to a thread of its own.
+        const CHUNK_SIZE: usize = 256;
+
+        let mut in_subgame = vec![false; self.len()];
+        for &v in subgame {
+            in_subgame[v as usize] = true;
+        }
+
+        let attracted = self.nodes().map(|v| AtomicBool::new(target.contains(&v))).collect::<Vec<_>>();
+        // The number of successors of an opponent node that have not been attracted yet.
+        let remaining = self
+            .nodes()
+            .map(|v| {
+                let count = if in_subgame[v as usize] && self.owner(v) != owner {
+                    self.succ(v).iter().filter(|x| in_subgame[**x as usize]).count()
+                } else {
+                    0
+                };
+                AtomicUsize::new(count)
+            })
+            .collect::<Vec<_>>();
+
+        let mut nodes = target.clone();
+        let mut strategy = PositionalStrategy::new();
+        let mut layer = target.iter().cloned().collect::<Vec<u32>>();
+
+        while !layer.is_empty() {
+            let chunk_size = cmp::max(CHUNK_SIZE, layer.len().div_ceil(threads));
+            let (in_subgame, attracted, remaining) = (&in_subgame, &attracted, &remaining);
+
+            let parts = thread::scope(|scope| {
+                let handles = layer
+                    .chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || {
+                        let mut next = Vec::new();
+                        let mut choices = Vec::new();
+
+                        for &w in chunk {
+                            for &v in self.pred(w) {
+                                if !in_subgame[v as usize] || attracted[v as usize].load(atomic::Ordering::SeqCst) {
+                                    continue;
+                                }
+
+                                if self.owner(v) == owner {
+                                    if !attracted[v as usize].swap(true, atomic::Ordering::SeqCst) {
+                                        choices.push((v, w));
+                                        next.push(v);
+                                    }
+                                } else if remaining[v as usize].fetch_sub(1, atomic::Ordering::SeqCst) == 1 {
+                                    attracted[v as usize].store(true, atomic::Ordering::SeqCst);
+                                    next.push(v);
+                                }
+                            }
+                        }
+
+                        (next, choices)
+                    }))
+                    .collect::<Vec<_>>();
+
+                handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+            });
+
+            layer = Vec::new();
+            for (next, choices) in parts {
+                nodes.extend(&next);
+                strategy.0.extend(choices);
+                layer.extend(next);
+            }
+        }
+
+        Attractor {
+            nodes,
+            strategy
         }
     }
 
-    pub fn node(&self, id: &u32) -> &Node {
-        &self.id_to_node[id]
+    /// Computes the strongly connected components of the subgame induced by the provided nodes, in reverse
+    /// topological order.
+    pub fn sccs(&self, subgame: &HashSet<u32>) -> Vec<HashSet<u32>> {
+        scc::tarjan(subgame, |v| self.succ(v))
     }
 
-    pub fn nodes(&self) -> HashSet<&Node> {
-        self.id_to_node
-            .values()
-            .collect::<HashSet<&Node>>()
+    /// Creates the game induced by the provided nodes, in which the nodes keep their identifiers.
+    ///
+    /// The nodes are numbered in the order of their index in this game. Edges that leave the provided nodes are left out.
+    pub fn subgame(&self, nodes: &HashSet<u32>) -> Game {
+        let mut vs = nodes.iter().cloned().collect::<Vec<u32>>();
+        vs.sort();
+
+        let index = vs
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i as u32))
+            .collect::<HashMap<u32, u32>>();
+        let succ = vs
+            .iter()
+            .map(|v| self.succ(*v).iter().filter_map(|w| index.get(w)).cloned().collect())
+            .collect();
+
+        let ids = vs.iter().map(|v| self.id(*v)).collect::<Vec<u32>>();
+        let id_to_index = ids.iter().enumerate().map(|(i, id)| (*id, i as u32)).collect();
+        let prio = vs.iter().map(|v| self.prio(*v)).collect();
+        let owner = vs.iter().map(|v| self.owner(*v)).collect();
+        let name = vs.iter().map(|v| self.name[*v as usize].clone()).collect();
+
+        Game::build(ids, id_to_index, prio, owner, name, succ, None, Vec::new(), Vec::new())
+    }
+
+    /// Creates the game induced by the provided nodes of the arena, in which the node at position `i` gets index and
+    /// identifier `i`. Edges that leave the provided nodes are left out.
+    pub fn from_arena<A: Arena>(arena: &A, nodes: &[A::Node]) -> Game {
+        let index = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i as u32))
+            .collect::<HashMap<A::Node, u32>>();
+        let succ = nodes
+            .iter()
+            .map(|v| {
+                let mut succ_v = arena.successors(*v).filter_map(|w| index.get(&w).cloned()).collect::<Vec<u32>>();
+                succ_v.sort();
+                succ_v
+            })
+            .collect();
+
+        let ids = (0..nodes.len() as u32).collect::<Vec<u32>>();
+        let id_to_index = ids.iter().map(|i| (*i, *i)).collect();
+        let prio = nodes.iter().map(|v| arena.prio(*v)).collect();
+        let owner = nodes.iter().map(|v| arena.owner(*v)).collect();
+        let name = vec![None; nodes.len()];
+
+        Game::build(ids, id_to_index, prio, owner, name, succ, None, Vec::new(), Vec::new())
+    }
+
+    /// Creates a copy of the game in which the nodes have the provided priorities, indexed by node.
+    pub fn with_prio(&self, prio: Vec<u32>) -> Game {
+        let succ = self.nodes().map(|v| self.succ(v).to_vec()).collect();
+
+        Game::build(self.ids.clone(),
+                    self.id_to_index.clone(),
+                    prio,
+                    self.owner.clone(),
+                    self.name.clone(),
+                    succ,
+                    None,
+                    Vec::new(),
+                    Vec::new())
+    }
+
+    /// Adds an edge from node `v` to node `w` and returns `true`, or returns `false` if the edge is already there.
+    ///
+    /// The flat arrays of successors and predecessors are rebuilt, which takes time linear in the size of the game.
+    pub fn add_edge(&mut self, v: u32, w: u32) -> bool {
+        if self.succ(v).contains(&w) {
+            return false;
+        }
+
+        let mut succ = self.nodes().map(|u| self.succ(u).to_vec()).collect::<Vec<_>>();
+        succ[v as usize].push(w);
+        succ[v as usize].sort();
+        self.set_succ(succ);
+
+        true
+    }
+
+    /// Removes the edge from node `v` to node `w` and returns `true`, or returns `false` if the edge is not there or
+    /// is the only edge of `v`, as the solvers do not accept nodes without successors.
+    ///
+    /// The flat arrays of successors and predecessors are rebuilt, which takes time linear in the size of the game.
+    pub fn remove_edge(&mut self, v: u32, w: u32) -> bool {
+        if !self.succ(v).contains(&w) || self.succ(v).len() == 1 {
+            return false;
+        }
+
+        let mut succ = self.nodes().map(|u| self.succ(u).to_vec()).collect::<Vec<_>>();
+        succ[v as usize].retain(|x| *x != w);
+        self.set_succ(succ);
+
+        true
+    }
+
+    /// Replaces the successors of every node.
+    fn set_succ(&mut self, succ: Vec<Vec<u32>>) {
+        let (succ_offsets, succ_flat) = Game::_flatten(&succ);
+        let (pred_offsets, pred_flat) = Game::_flatten(&Game::_pred(&succ));
+
+        self.succ_offsets = succ_offsets;
+        self.succ = succ_flat;
+        self.pred_offsets = pred_offsets;
+        self.pred = pred_flat;
+    }
+
+    /// Changes the priority of node `v`, which may change the maximal priority and the maximal measures.
+    pub fn set_prio(&mut self, v: u32, prio: u32) {
+        self.prio[v as usize] = prio;
+        self.max_prio = Game::_max_prio(&self.prio);
+        self.max_measure_even = Game::_max_measure(&self.prio, self.max_prio, Owner::Even);
+        self.max_measure_odd = Game::_max_measure(&self.prio, self.max_prio, Owner::Odd);
+    }
+
+    pub fn set_owner(&mut self, v: u32, owner: Owner) {
+        self.owner[v as usize] = owner;
     }
 
     pub fn new_measure(&self) -> MeasureT {
@@ -90,14 +575,12 @@ impl Game {
         MeasureT::Measure(m)
     }
 
-    pub fn new_progress(&self) -> Progress {
-        let mut m = HashMap::new();
-
-        for node in self.nodes() {
-            m.insert(node.id, self.new_measure());
+    /// Returns the progress measure of the player in which every node has the zero measure.
+    pub fn new_progress(&self, player: Owner) -> Progress {
+        Progress {
+            player,
+            measures: vec![self.new_measure(); self.len()]
         }
-
-        Progress(m)
     }
 
     /// Returns the maximal priority of any node in the game.
@@ -107,48 +590,63 @@ impl Game {
         self.max_prio
     }
 
-    pub fn max_measure(&self) -> &Measure {
-        &self.max_measure
+    /// Returns the maximal measure of the player, which counts the nodes of every priority of its opponent.
+    pub fn max_measure(&self, player: Owner) -> &Measure {
+        match player {
+            Owner::Even => &self.max_measure_even,
+            Owner::Odd  => &self.max_measure_odd,
+        }
     }
 
-    fn _nodes_with_prio(id_to_node: &HashMap<u32, Node>, prio: u32) -> HashSet<&Node> {
-        id_to_node
-            .values()
-            .filter(|&n| n.prio == prio)
-            .collect::<HashSet<&Node>>()
-    }
-    
-    fn _max_prio(id_to_node: &HashMap<u32, Node>) -> u32 {
-        id_to_node
-            .values()
-            .map(|n| n.prio)
-            .max()
-            .unwrap_or(0)
-    }
+    /// Returns the predecessors of every node, given the successors of every node.
+    fn _pred(succ: &[Vec<u32>]) -> Vec<Vec<u32>> {
+        let mut pred = vec![Vec::new(); succ.len()];
+        for (v, succ_v) in succ.iter().enumerate() {
+            for w in succ_v {
+                pred[*w as usize].push(v as u32);
+            }
+        }
 
-    fn _max_measure(id_to_node: &HashMap<u32, Node>, max_prio: u32) -> Measure {
-        let prio = max_prio.clone();
-        let mut measure = vec![0; prio as usize + 1];
+        pred
+    }
 
-        // Get the last odd index of the measure.
-        let mut i = prio as i32;
+    /// Concatenates the lists into a flat array, together with the offset at which every list starts.
+    fn _flatten(lists: &[Vec<u32>]) -> (Vec<usize>, Vec<u32>) {
+        let mut offsets = Vec::with_capacity(lists.len() + 1);
+        let mut flat = Vec::with_capacity(lists.iter().map(|l| l.len()).sum());
 
-        if i % 2 == 0 {
-            i -= 1;
+        offsets.push(0);
+        for list in lists {
+            flat.extend_from_slice(list);
+            offsets.push(flat.len());
         }
 
-        while i >= 1 {
-            let nr_of_nodes_with_prio = Game::_nodes_with_prio(id_to_node, i as u32).len(); 
-            measure[i as usize] = nr_of_nodes_with_prio as u32;
+        (offsets, flat)
+    }
+
+    fn _max_prio(prio: &[u32]) -> u32 {
+        prio
+            .iter()
+            .cloned()
+            .max()
+            .unwrap_or(0)
+    }
 
-            i -= 2;
+    fn _max_measure(prio: &[u32], max_prio: u32, player: Owner) -> Measure {
+        let mut measure = vec![0; max_prio as usize + 1];
+
+        // Only the priorities of the opponent are counted.
+        for p in prio {
+            if Owner::of_prio(*p) != player {
+                measure[*p as usize] += 1;
+            }
         }
 
         Measure(measure)
     }
 }
 
-#[derive(Debug, Eq, Clone, Ord)]
+#[derive(Debug, Eq, Clone)]
 pub struct Measure(pub Vec<u32>);
 
 impl Measure {
@@ -227,20 +725,25 @@ impl Measure {
     // }
 }
 
-impl PartialOrd for Measure {
-    fn partial_cmp(&self, other: &Measure) -> Option<Ordering> {
+impl Ord for Measure {
+    fn cmp(&self, other: &Measure) -> Ordering {
         let max_l = cmp::max(self.length(), other.length());
 
         if self.eq(other, max_l - 1) {
-            Some(Ordering::Equal)
+            Ordering::Equal
         }
         else if self.gt(other, max_l - 1) {
-            Some(Ordering::Greater)
+            Ordering::Greater
         } else {
-            Some(Ordering::Less)
+            Ordering::Less
         }
     }
 }
+impl PartialOrd for Measure {
+    fn partial_cmp(&self, other: &Measure) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 impl PartialEq for Measure {
     fn eq(&self, other: &Measure) -> bool {
         let max_l = cmp::max(self.length(), other.length());
@@ -250,7 +753,7 @@ impl PartialEq for Measure {
 }
 
 /// A measure that can also be the special value `Top`.
-#[derive(Debug, Eq, Ord, Clone)]
+#[derive(Debug, Eq, Clone)]
 pub enum MeasureT {
     /// The value `Top` that is greater than any `Measure`.
     Top,
@@ -260,9 +763,10 @@ pub enum MeasureT {
 
 impl MeasureT {
 
-    pub fn inc(&self, game: &Game) -> MeasureT {
+    /// Returns the least measure of the player that is greater than this measure, or `Top` if there is none.
+    pub fn inc(&self, game: &Game, player: Owner) -> MeasureT {
 
-        fn inc_measure(cur: &Measure, max: &Measure) -> MeasureT {
+        fn inc_measure(cur: &Measure, max: &Measure, player: Owner) -> MeasureT {
             if cur == max {
                 // It is the maximal value, return `Top`.
                 MeasureT::Top
@@ -272,13 +776,13 @@ impl MeasureT {
                 let mut new = cur.clone();
                 let mut i = cur.length() as i32 - 1;
 
-                // We can only increase the odd numbers, so if even go the the closest odd number.
-                if i % 2 == 0 {
+                // We can only increase the numbers of the opponent, so go to the closest priority of the opponent.
+                if Owner::of_prio(i as u32) == player {
                     i -= 1;
                 }
 
                 // Move backwards through the vector and increase the value where possible.
-                while i >= 1 {
+                while i >= 0 {
                     let max_v = max.0[i as usize];
                     let cur_v = cur.0[i as usize];
 
@@ -291,7 +795,7 @@ impl MeasureT {
                         new.0[i as usize] = 0;
                     }
 
-                    // Only the odd numbers can change.
+                    // Only the numbers of the opponent can change.
                     i -= 2;
                 }
 
@@ -301,10 +805,55 @@ impl MeasureT {
 
         match self {
             &MeasureT::Top        => MeasureT::Top,
-            &MeasureT::Measure(ref m) => inc_measure(m, &game.max_measure())
+            &MeasureT::Measure(ref m) => inc_measure(m, game.max_measure(player), player)
         }
     }
 
+    /// Returns this measure with all values after the specified index set to 0.
+    pub fn truncate(&self, i: usize) -> MeasureT {
+        match *self {
+            MeasureT::Top            => MeasureT::Top,
+            MeasureT::Measure(ref m) => {
+                let mut new = m.clone();
+                for x in (i + 1)..new.length() {
+                    new.0[x] = 0;
+                }
+
+                MeasureT::Measure(new)
+            }
+        }
+    }
+
+    /// Returns the least measure that is greater than this measure up to and including the specified index.
+    ///
+    /// This is the truncation of this measure at the index, increased once in the values of the opponent of the player
+    /// up to the index. If these values are all maximal, `Top` is returned.
+    pub fn inc_up_to(&self, i: usize, game: &Game, player: Owner) -> MeasureT {
+        let mut new = match self.truncate(i) {
+            MeasureT::Top     => return MeasureT::Top,
+            MeasureT::Measure(m) => m,
+        };
+        let max = game.max_measure(player);
+
+        // We can only increase the numbers of the opponent, so go to the closest priority of the opponent.
+        let mut x = i as i32;
+        if Owner::of_prio(i as u32) == player {
+            x -= 1;
+        }
+
+        while x >= 0 {
+            if new.0[x as usize] < max.0[x as usize] {
+                new.0[x as usize] += 1;
+                return MeasureT::Measure(new);
+            }
+
+            new.0[x as usize] = 0;
+            x -= 2;
+        }
+
+        MeasureT::Top
+    }
+
     /// Returns `true` if this measure is equal to the provided measure up to and including the specified index.
     /// Otherwise `false` is returned.
     pub fn eq(&self, other: &MeasureT, i: usize) -> bool {
@@ -346,16 +895,22 @@ impl MeasureT {
     }
 }
 
-impl PartialOrd for MeasureT {
-    fn partial_cmp(&self, other: &MeasureT) -> Option<Ordering> {
+// `Ord` is implemented by hand, a derived implementation would order `Top` below every `Measure`.
+impl Ord for MeasureT {
+    fn cmp(&self, other: &MeasureT) -> Ordering {
         match (self, other) {
-            (&MeasureT::Top, &MeasureT::Top)                                  => Some(Ordering::Equal),
-            (&MeasureT::Top, _)                                               => Some(Ordering::Greater),
-            (_, &MeasureT::Top)                                               => Some(Ordering::Less),
-            (&MeasureT::Measure(ref self_m), &MeasureT::Measure(ref other_m)) => self_m.partial_cmp(other_m),
+            (&MeasureT::Top, &MeasureT::Top)                                  => Ordering::Equal,
+            (&MeasureT::Top, _)                                               => Ordering::Greater,
+            (_, &MeasureT::Top)                                               => Ordering::Less,
+            (&MeasureT::Measure(ref self_m), &MeasureT::Measure(ref other_m)) => self_m.cmp(other_m),
         }
     }
 }
+impl PartialOrd for MeasureT {
+    fn partial_cmp(&self, other: &MeasureT) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 impl PartialEq for MeasureT {
     fn eq(&self, other: &MeasureT) -> bool {
         match (self, other) {
@@ -367,53 +922,27 @@ impl PartialEq for MeasureT {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Progress(pub HashMap<u32, MeasureT>);
+/// The measures of every node for the player, where the nodes with measure `Top` are won by its opponent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub player: Owner,
+    pub measures: Vec<MeasureT>
+}
 
 impl Progress {
-    pub fn nodes(&self) -> HashSet<&u32> {
-        self.0
-            .keys()
-            .collect::<HashSet<&u32>>()
-    }
-
-    pub fn measure(&self, node_id: &u32) -> &MeasureT {
-        &self.0[node_id]
+    pub fn nodes(&self) -> Range<u32> {
+        0..self.measures.len() as u32
     }
 
-    pub fn winning_set(&self, owner: Owner) -> HashSet<&u32> {
-        let mut all = self.0.iter();
-
-        match owner {
-            Owner::Even => all
-                .filter(|&(_, m)| m != &MeasureT::Top)
-                .map(|(id, _)| id)
-                .collect(),
-            Owner::Odd => all
-                .filter(|&(_, m)| m == &MeasureT::Top)
-                .map(|(id, _)| id)
-                .collect(),
-        }
+    pub fn measure(&self, v: u32) -> &MeasureT {
+        &self.measures[v as usize]
     }
-}
-impl PartialEq for Progress {
-    fn eq(&self, other: &Progress) -> bool {
-        let self_v = self.nodes();
-        let other_v = self.nodes();
-
-        if self_v != other_v {
-            return false;
-        }
 
-        for node_id in self_v {
-            let self_m = self.measure(node_id);
-            let other_m = other.measure(node_id);
+    pub fn winning_set(&self, owner: Owner) -> HashSet<u32> {
+        let won_by_opponent = owner != self.player;
 
-            if self_m != other_m {
-                return false;
-            }
-        }
-
-        true
+        self.nodes()
+            .filter(|v| (self.measure(*v) == &MeasureT::Top) == won_by_opponent)
+            .collect()
     }
 }
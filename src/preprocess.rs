@@ -0,0 +1,213 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+use solver::{Solution, Solver, TimedOut};
+
+/// The size of a game, as far as the solvers are concerned.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Size {
+    pub nodes: usize,
+    pub edges: usize,
+    /// The number of distinct priorities.
+    pub prios: usize,
+    pub max_prio: u32
+}
+
+impl Size {
+    pub fn of(game: &Game) -> Size {
+        Size {
+            nodes: game.len(),
+            edges: game.nodes().map(|v| game.succ(v).len()).sum(),
+            prios: game.nodes().map(|v| game.prio(v)).collect::<HashSet<u32>>().len(),
+            max_prio: game.max_prio()
+        }
+    }
+}
+
+/// The size of the game before and after a preprocessing step.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Reduction {
+    pub step: &'static str,
+    pub before: Size,
+    pub after: Size
+}
+
+impl Reduction {
+    fn new(step: &'static str, before: &Game, after: &Game) -> Reduction {
+        Reduction {
+            step,
+            before: Size::of(before),
+            after: Size::of(after)
+        }
+    }
+}
+
+pub struct PreprocessingResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// How much every preprocessing step shrank the game, in the order in which they were applied.
+    pub reductions: Vec<Reduction>
+}
+
+/// Decides the nodes with a self-loop that is won by the player, together with the attractor of the player to them.
+///
+/// A self-loop is won by the player of the priority of its node if the player owns the node, or if the self-loop is
+/// the only edge of the node. The decided nodes are removed from the remaining nodes.
+fn decide_self_loops(game: &Game,
+                     player: Owner,
+                     remaining: &mut HashSet<u32>,
+                     won: &mut HashSet<u32>,
+                     strategy: &mut PositionalStrategy) {
+    let loops = remaining
+        .iter()
+        .filter(|v| Owner::of_prio(game.prio(**v)) == player)
+        .filter(|v| {
+            let succ = game.succ(**v);
+            succ.contains(v) && (game.owner(**v) == player || succ.len() == 1)
+        })
+        .cloned()
+        .collect::<HashSet<u32>>();
+
+    for &v in &loops {
+        if game.owner(v) == player {
+            strategy.0.insert(v, v);
+        }
+    }
+
+    let a = game.attractor(player, remaining, &loops);
+    strategy.0.extend(a.strategy.0);
+
+    for v in &a.nodes {
+        remaining.remove(v);
+    }
+    won.extend(a.nodes);
+}
+
+/// Returns the priorities of the nodes after propagating them along the edges of the game.
+///
+/// If a node is visited infinitely often, so is one of its successors. If all successors have a priority at least as
+/// significant as the node, one of them decides the play whenever the node could, so the priority of the node can be
+/// lowered to the least significant priority of its successors. The same holds for its predecessors. Lowering a
+/// priority may allow its neighbours to be lowered, so this is repeated until no priority changes. A node with a
+/// self-loop is its own successor, so its priority is never changed.
+fn propagate(game: &Game) -> Vec<u32> {
+    let mut prio = game.nodes().map(|v| game.prio(v)).collect::<Vec<u32>>();
+
+    loop {
+        let mut any_change = false;
+
+        for v in game.nodes() {
+            let mut p = prio[v as usize];
+
+            if let Some(max_succ) = game.succ(v).iter().map(|w| prio[*w as usize]).max() {
+                p = cmp::min(p, max_succ);
+            }
+            if let Some(max_pred) = game.pred(v).iter().map(|u| prio[*u as usize]).max() {
+                p = cmp::min(p, max_pred);
+            }
+
+            if p < prio[v as usize] {
+                prio[v as usize] = p;
+                any_change = true;
+            }
+        }
+
+        if !any_change {
+            return prio;
+        }
+    }
+}
+
+/// Returns the priorities of the nodes after removing the gaps between them and merging neighbouring priorities of
+/// the same parity, which keeps the parity of every priority and the order between priorities of different parity.
+fn compress(game: &Game) -> Vec<u32> {
+    let mut prios = game.nodes().map(|v| game.prio(v)).collect::<Vec<u32>>();
+    prios.sort();
+    prios.dedup();
+
+    let mut compressed = HashMap::new();
+    let mut next = 0;
+
+    for p in prios {
+        if Owner::of_prio(next) != Owner::of_prio(p) {
+            next += 1;
+        }
+
+        compressed.insert(p, next);
+    }
+
+    game.nodes().map(|v| compressed[&game.prio(v)]).collect()
+}
+
+/// Decides the winning self-loops and their attractors, then propagates and compresses the priorities of the
+/// remaining nodes.
+///
+/// Returns the game that remains to be solved, in which the nodes keep their identifiers, together with the nodes
+/// that were decided and the reduction of every step.
+pub fn preprocess(game: &Game) -> (Game, PreprocessingResult) {
+    let mut remaining = game.nodes().collect::<HashSet<u32>>();
+    let mut result = PreprocessingResult {
+        won_even: HashSet::new(),
+        won_odd: HashSet::new(),
+        strategy_even: PositionalStrategy::new(),
+        strategy_odd: PositionalStrategy::new(),
+        reductions: Vec::new()
+    };
+
+    decide_self_loops(game, Owner::Even, &mut remaining, &mut result.won_even, &mut result.strategy_even);
+    decide_self_loops(game, Owner::Odd, &mut remaining, &mut result.won_odd, &mut result.strategy_odd);
+    let reduced = game.subgame(&remaining);
+    result.reductions.push(Reduction::new("Self-loops", game, &reduced));
+
+    let propagated = reduced.with_prio(propagate(&reduced));
+    result.reductions.push(Reduction::new("Propagation", &reduced, &propagated));
+
+    let compressed = propagated.with_prio(compress(&propagated));
+    result.reductions.push(Reduction::new("Compression", &propagated, &compressed));
+
+    (compressed, result)
+}
+
+/// Preprocesses the game and solves the game that remains with the inner solver.
+///
+/// The solution of the remaining game is mapped back to the nodes of this game by their identifiers. The statistics
+/// count how many nodes, edges and priorities every step removed. Every node in the game must have a successor. The
+/// token is passed on to the inner solver.
+pub fn solve_preprocessed(game: &Game, inner: &dyn Solver, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+    let (reduced, preprocessed) = preprocess(game);
+    let mut result = Solution::new(preprocessed.won_even,
+                                   preprocessed.won_odd,
+                                   preprocessed.strategy_even,
+                                   preprocessed.strategy_odd);
+
+    for r in &preprocessed.reductions {
+        result.statistics.insert(format!("{}: removed nodes", r.step), (r.before.nodes - r.after.nodes) as u64);
+        result.statistics.insert(format!("{}: removed edges", r.step), (r.before.edges - r.after.edges) as u64);
+        result.statistics.insert(format!("{}: removed priorities", r.step), (r.before.prios - r.after.prios) as u64);
+        let lowered = r.before.max_prio - r.after.max_prio;
+        result.statistics.insert(format!("{}: lowered maximal priority", r.step), lowered as u64);
+    }
+
+    if reduced.is_empty() {
+        return Ok(result);
+    }
+
+    let solution = match inner.solve_cancellable(&reduced, cancel) {
+        Ok(solution) => solution,
+        Err(timed_out) => return Err(timed_out.add_statistics(&result.statistics)),
+    };
+    let index = |v: &u32| game.index(reduced.id(*v)).unwrap();
+
+    result.won_even.extend(solution.won_even.iter().map(&index));
+    result.won_odd.extend(solution.won_odd.iter().map(&index));
+    result.strategy_even.0.extend(solution.strategy_even.0.iter().map(|(v, w)| (index(v), index(w))));
+    result.strategy_odd.0.extend(solution.strategy_odd.0.iter().map(|(v, w)| (index(v), index(w))));
+    result.add_statistics(&solution.statistics);
+
+    Ok(result)
+}
@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+
+pub struct PriorityPromotionResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// The number of closed regions that were merged into a region with a more significant priority.
+    pub nr_of_promotions: u64,
+    /// The number of regions that were dissolved again, because a region was promoted past them.
+    pub nr_of_resets: u64
+}
+
+/// The statistics of priority promotion that was cancelled before it solved the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PriorityPromotionTimedOut {
+    pub nr_of_promotions: u64,
+    pub nr_of_resets: u64
+}
+
+/// A dominion found by the search, together with the strategy of its player that keeps every play within it.
+struct Dominion {
+    player: Owner,
+    nodes: HashSet<u32>,
+    strategy: PositionalStrategy
+}
+
+/// Searches the remaining nodes of the game for a dominion of either player.
+///
+/// Every node is assigned the priority of the region it belongs to, which initially is its own priority. Starting at
+/// the most significant (lowest) priority, the player of the priority attracts to the nodes of that region. If the
+/// opponent can escape to a less significant region, the next priority is considered. If it can only escape to more
+/// significant regions, the region is promoted to the closest of those and every region in between is reset.
+/// A region from which the opponent cannot escape at all is a dominion. The token is checked before every attractor,
+/// which returns `None` once it is cancelled.
+fn search(game: &Game,
+          remaining: &HashSet<u32>,
+          cancel: &Cancellation,
+          nr_of_promotions: &mut u64,
+          nr_of_resets: &mut u64) -> Option<Dominion> {
+    let mut region = (0..game.len() as u32).map(|v| game.prio(v)).collect::<Vec<u32>>();
+    let mut strategy = PositionalStrategy::new();
+    let mut p = remaining.iter().map(|v| region[*v as usize]).min().unwrap();
+
+    loop {
+        if cancel.is_cancelled() {
+            return None;
+        }
+
+        let player = Owner::of_prio(p);
+        let subgame = remaining
+            .iter()
+            .filter(|v| region[**v as usize] >= p)
+            .cloned()
+            .collect::<HashSet<u32>>();
+        let base = subgame
+            .iter()
+            .filter(|v| region[**v as usize] == p)
+            .cloned()
+            .collect::<HashSet<u32>>();
+
+        let a = game.attractor(player, &subgame, &base);
+        let attracted = a.nodes;
+        strategy.0.extend(a.strategy.0);
+
+        for &v in &attracted {
+            region[v as usize] = p;
+        }
+
+        // The player must be able to stay in the region from the nodes it did not attract.
+        let mut open = false;
+
+        for &v in &base {
+            if game.owner(v) != player || strategy.successor(&v).is_some_and(|w| attracted.contains(w)) {
+                continue;
+            }
+
+            match game.succ(v).iter().find(|w| attracted.contains(w)) {
+                Some(w) => { strategy.0.insert(v, *w); },
+                None    => open = true,
+            }
+        }
+
+        // The opponent may not be able to escape to a less significant region.
+        open = open || attracted
+            .iter()
+            .filter(|v| game.owner(**v) != player)
+            .any(|v| game.succ(*v).iter().any(|w| subgame.contains(w) && !attracted.contains(w)));
+
+        if open {
+            p = subgame
+                .iter()
+                .filter(|v| !attracted.contains(v))
+                .map(|v| region[*v as usize])
+                .min()
+                .unwrap();
+            continue;
+        }
+
+        // The region is closed within the subgame, so the opponent can only escape to more significant regions.
+        let escape = attracted
+            .iter()
+            .filter(|v| game.owner(**v) != player)
+            .flat_map(|v| game.succ(*v).iter())
+            .filter(|w| remaining.contains(w) && !subgame.contains(w))
+            .map(|w| region[*w as usize])
+            .max();
+
+        let q = match escape {
+            Some(q) => q,
+            None    => {
+                let strategy = attracted
+                    .iter()
+                    .filter(|v| game.owner(**v) == player)
+                    .map(|v| (*v, *strategy.successor(v).unwrap()))
+                    .collect();
+
+                return Some(Dominion {
+                    player,
+                    nodes: attracted,
+                    strategy: PositionalStrategy(strategy)
+                });
+            }
+        };
+
+        // Promote the region to the closest region the opponent can escape to, and reset the regions in between.
+        *nr_of_promotions += 1;
+
+        for &v in &attracted {
+            region[v as usize] = q;
+        }
+
+        let reset = remaining
+            .iter()
+            .filter(|v| region[**v as usize] > q && region[**v as usize] < p)
+            .map(|v| region[*v as usize])
+            .collect::<HashSet<u32>>();
+        *nr_of_resets += reset.len() as u64;
+
+        for &v in remaining {
+            if region[v as usize] > q {
+                region[v as usize] = game.prio(v);
+                strategy.0.remove(&v);
+            }
+        }
+
+        p = q;
+    }
+}
+
+/// Solves the game using priority promotion.
+///
+/// Repeatedly searches for a dominion, removes everything its player can attract to it from the game, and starts over
+/// on the remainder. Every node in the game must have a successor. The token is checked before every attractor of the
+/// search. Once it is cancelled, the statistics so far are returned instead.
+pub fn priority_promotion(game: &Game,
+                          cancel: &Cancellation) -> Result<PriorityPromotionResult, PriorityPromotionTimedOut> {
+    let mut remaining = game.nodes().collect::<HashSet<u32>>();
+    let mut result = PriorityPromotionResult {
+        won_even: HashSet::new(),
+        won_odd: HashSet::new(),
+        strategy_even: PositionalStrategy::new(),
+        strategy_odd: PositionalStrategy::new(),
+        nr_of_promotions: 0,
+        nr_of_resets: 0
+    };
+
+    while !remaining.is_empty() {
+        let dominion = match search(game, &remaining, cancel, &mut result.nr_of_promotions, &mut result.nr_of_resets) {
+            Some(dominion) => dominion,
+            None           => return Err(PriorityPromotionTimedOut {
+                nr_of_promotions: result.nr_of_promotions,
+                nr_of_resets: result.nr_of_resets
+            }),
+        };
+        let a = game.attractor(dominion.player, &remaining, &dominion.nodes);
+
+        let (won, strategy) = match dominion.player {
+            Owner::Even => (&mut result.won_even, &mut result.strategy_even),
+            Owner::Odd  => (&mut result.won_odd, &mut result.strategy_odd),
+        };
+        strategy.0.extend(dominion.strategy.0);
+        strategy.0.extend(a.strategy.0);
+
+        for v in &a.nodes {
+            remaining.remove(v);
+        }
+        won.extend(a.nodes);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use pg::*;
+use algorithm;
+use algorithm::ProgSort;
+use cancel::Cancellation;
+
+/// A change to a game, in which nodes are given by their index.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Edit {
+    AddEdge(u32, u32),
+    RemoveEdge(u32, u32),
+    SetPrio(u32, u32),
+    SetOwner(u32, Owner)
+}
+
+pub struct ResolvingResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    /// The strategy of `Even`, which is empty if the measures are those of `Odd`.
+    pub strategy_even: PositionalStrategy,
+    /// The strategy of `Odd`, which is empty if the measures are those of `Even`.
+    pub strategy_odd: PositionalStrategy,
+    /// The number of lifts that increased a measure.
+    pub nr_of_iterations: u64,
+    /// The number of attempts to lift a node.
+    pub nr_of_subiterations: u64,
+    /// The number of nodes of which the measure was reset to zero because of the edits.
+    pub nr_of_reset_nodes: u64
+}
+
+/// Solves a game with small progress measures of one player, which is solved again after edits by lifting the
+/// measures of the previous solution.
+///
+/// The least fixpoint can only decrease at a node of the player that gains an edge, a node of the opponent that loses
+/// an edge, a node that becomes owned by the player or a node of which the priority changes. Only the nodes that can
+/// reach such a node are reset, all other measures remain lower bounds of the new least fixpoint.
+pub struct ResolvingSolver {
+    game: Game,
+    progress: Progress,
+    prog_sort: ProgSort,
+    /// Nodes of which the least fixpoint may have decreased since the last solution.
+    decreased: HashSet<u32>,
+    /// Nodes that were edited since the last solution, which may be lifted further.
+    edited: HashSet<u32>
+}
+
+impl ResolvingSolver {
+    pub fn new(game: Game, player: Owner, prog_sort: ProgSort) -> ResolvingSolver {
+        let progress = game.new_progress(player);
+        let edited = game.nodes().collect();
+
+        ResolvingSolver {
+            game,
+            progress,
+            prog_sort,
+            decreased: HashSet::new(),
+            edited
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Applies the edit to the game and returns `true`, or returns `false` if the edit does not change the game.
+    ///
+    /// Edges are added and removed as by `Game::add_edge` and `Game::remove_edge`.
+    pub fn apply(&mut self, edit: Edit) -> bool {
+        let player = self.progress.player;
+
+        let (v, changed, decreases) = match edit {
+            Edit::AddEdge(v, w)        => (v, self.game.add_edge(v, w), self.game.owner(v) == player),
+            Edit::RemoveEdge(v, w)     => (v, self.game.remove_edge(v, w), self.game.owner(v) != player),
+            Edit::SetPrio(v, prio)     => {
+                let changed = self.game.prio(v) != prio;
+                self.game.set_prio(v, prio);
+                (v, changed, true)
+            },
+            Edit::SetOwner(v, owner)   => {
+                let changed = self.game.owner(v) != owner;
+                self.game.set_owner(v, owner);
+                (v, changed, owner == player)
+            },
+        };
+
+        if changed {
+            self.edited.insert(v);
+
+            if decreases {
+                self.decreased.insert(v);
+            }
+        }
+
+        changed
+    }
+
+    /// Solves the game, starting from the measures of the previous solution.
+    ///
+    /// The nodes that can reach a node of which the least fixpoint may have decreased are reset to the zero measure,
+    /// and the measures are resized to the maximal priority of the game. Only the reset and edited nodes are put on the
+    /// worklist, as the measures of all other nodes were stable.
+    pub fn solve(&mut self) -> ResolvingResult {
+        let reset = self.reachable_to(&self.decreased);
+        let len = self.game.max_prio() as usize + 1;
+        let mut progress = self.progress.clone();
+
+        for v in self.game.nodes() {
+            if reset.contains(&v) {
+                progress.measures[v as usize] = self.game.new_measure();
+            } else if let MeasureT::Measure(ref mut m) = progress.measures[v as usize] {
+                m.0.resize(len, 0);
+            }
+        }
+
+        let mut worklist = reset.union(&self.edited).cloned().collect::<Vec<u32>>();
+        worklist.sort();
+
+        let result = algorithm::small_progress_measures_from(&self.game,
+                                                             progress,
+                                                             worklist,
+                                                             self.prog_sort,
+                                                             &Cancellation::new())
+            .unwrap_or_else(|_| unreachable!("The token is never cancelled."));
+
+        self.progress = match self.progress.player {
+            Owner::Even => result.progress_even.unwrap(),
+            Owner::Odd  => result.progress_odd.unwrap(),
+        };
+        self.decreased.clear();
+        self.edited.clear();
+
+        ResolvingResult {
+            won_even: result.won_even,
+            won_odd: result.won_odd,
+            strategy_even: result.strategy_even,
+            strategy_odd: result.strategy_odd,
+            nr_of_iterations: result.nr_of_iterations,
+            nr_of_subiterations: result.nr_of_subiterations,
+            nr_of_reset_nodes: reset.len() as u64
+        }
+    }
+
+    /// Returns the nodes that can reach the target nodes, including the target nodes.
+    fn reachable_to(&self, target: &HashSet<u32>) -> HashSet<u32> {
+        let mut nodes = target.clone();
+        let mut stack = target.iter().cloned().collect::<Vec<u32>>();
+
+        while let Some(w) = stack.pop() {
+            for &v in self.game.pred(w) {
+                if nodes.insert(v) {
+                    stack.push(v);
+                }
+            }
+        }
+
+        nodes
+    }
+}
@@ -0,0 +1,154 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+use solver::{Solution, Solver, TimedOut};
+
+/// Computes the strongly connected components of the graph given by the successors of the provided nodes, restricted
+/// to those nodes.
+///
+/// This is an iterative version of Tarjan's algorithm, so large components do not overflow the stack. The components
+/// are returned in reverse topological order: every component comes after all components it can reach.
+pub fn tarjan<'a, F>(nodes: &HashSet<u32>, succ: F) -> Vec<HashSet<u32>> where F: Fn(u32) -> &'a [u32] {
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    let mut lowlink: HashMap<u32, usize> = HashMap::new();
+    let mut on_stack = HashSet::new();
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for root in nodes {
+        if index.contains_key(root) {
+            continue;
+        }
+
+        // Every frame holds a node and the position of the next successor to visit.
+        let mut frames = vec![(*root, 0)];
+        index.insert(*root, next_index);
+        lowlink.insert(*root, next_index);
+        next_index += 1;
+        stack.push(*root);
+        on_stack.insert(*root);
+
+        while let Some(&(v, i)) = frames.last() {
+            let succ = succ(v);
+
+            if i < succ.len() {
+                frames.last_mut().unwrap().1 += 1;
+                let w = succ[i];
+
+                if !nodes.contains(&w) {
+                    continue;
+                }
+
+                if let Some(&w_index) = index.get(&w) {
+                    if on_stack.contains(&w) {
+                        let low = cmp::min(lowlink[&v], w_index);
+                        lowlink.insert(v, low);
+                    }
+                } else {
+                    index.insert(w, next_index);
+                    lowlink.insert(w, next_index);
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack.insert(w);
+                    frames.push((w, 0));
+                }
+
+                continue;
+            }
+
+            frames.pop();
+
+            if let Some(&(u, _)) = frames.last() {
+                let low = cmp::min(lowlink[&u], lowlink[&v]);
+                lowlink.insert(u, low);
+            }
+
+            if lowlink[&v] == index[&v] {
+                let mut component = HashSet::new();
+
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack.remove(&w);
+                    component.insert(w);
+
+                    if w == v {
+                        break;
+                    }
+                }
+
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Solves the game one strongly connected component at a time, starting with the components that cannot leave.
+///
+/// The nodes of a component that are not yet decided are solved as a game on their own by the inner solver, after
+/// which both players attract to the regions they won among the undecided nodes of the game. The statistics of the
+/// inner solver are summed over all components. Every node in the game must have a successor. The token is passed on
+/// to the inner solver, and checked before every component.
+///
+/// A node that remains undecided after the attractors has a successor within its own component, as all components it
+/// can reach have been decided before it, so the undecided nodes of a component never contain dead ends.
+pub fn solve_bottom_up(game: &Game, inner: &dyn Solver, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+    let mut undecided = game.nodes().collect::<HashSet<u32>>();
+    let components = game.sccs(&undecided);
+    let nr_of_components = components.len() as u64;
+    let mut nr_of_solved_components = 0;
+    let mut result = Solution::new(HashSet::new(),
+                                   HashSet::new(),
+                                   PositionalStrategy::new(),
+                                   PositionalStrategy::new());
+
+    for component in components {
+        let rest = component.intersection(&undecided).cloned().collect::<HashSet<u32>>();
+        if rest.is_empty() {
+            continue;
+        }
+
+        if cancel.is_cancelled() {
+            return Err(TimedOut { statistics: result.statistics });
+        }
+
+        nr_of_solved_components += 1;
+
+        let subgame = game.subgame(&rest);
+        let solution = match inner.solve_cancellable(&subgame, cancel) {
+            Ok(solution) => solution,
+            Err(timed_out) => return Err(timed_out.add_statistics(&result.statistics)),
+        };
+        result.add_statistics(&solution.statistics);
+
+        // The nodes of the subgame keep their identifiers, by which they are mapped back to this game.
+        let index = |v: &u32| game.index(subgame.id(*v)).unwrap();
+        let won_even = solution.won_even.iter().map(&index).collect::<HashSet<u32>>();
+        let won_odd = solution.won_odd.iter().map(&index).collect::<HashSet<u32>>();
+        result.strategy_even.0.extend(solution.strategy_even.0.iter().map(|(v, w)| (index(v), index(w))));
+        result.strategy_odd.0.extend(solution.strategy_odd.0.iter().map(|(v, w)| (index(v), index(w))));
+
+        let a = game.attractor(Owner::Even, &undecided, &won_even);
+        for v in &a.nodes {
+            undecided.remove(v);
+        }
+        result.won_even.extend(a.nodes);
+        result.strategy_even.0.extend(a.strategy.0);
+
+        let a = game.attractor(Owner::Odd, &undecided, &won_odd);
+        for v in &a.nodes {
+            undecided.remove(v);
+        }
+        result.won_odd.extend(a.nodes);
+        result.strategy_odd.0.extend(a.strategy.0);
+    }
+
+    Ok(result
+        .with("Number of components", nr_of_components)
+        .with("Number of solved components", nr_of_solved_components))
+}
@@ -0,0 +1,398 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use pg::*;
+use algorithm;
+use algorithm::ProgSort;
+use cancel::Cancellation;
+use fixpoint;
+use fixpoint::ResetSort;
+use preprocess;
+use priority_promotion;
+use scc;
+use strategies;
+use strategy_improvement;
+use strategy_improvement::ImprovementRule;
+use succinct;
+use tangle_learning;
+use zielonka;
+
+/// The winning regions and strategies of both players, together with statistics of the solver that found them.
+pub struct Solution {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// Counters of the solver by their description, such as "Number of iterations".
+    pub statistics: BTreeMap<String, u64>
+}
+
+impl Solution {
+    pub fn new(won_even: HashSet<u32>,
+               won_odd: HashSet<u32>,
+               strategy_even: PositionalStrategy,
+               strategy_odd: PositionalStrategy) -> Solution {
+        Solution {
+            won_even,
+            won_odd,
+            strategy_even,
+            strategy_odd,
+            statistics: BTreeMap::new()
+        }
+    }
+
+    /// Returns this solution with the counter added.
+    pub fn with(mut self, name: &str, value: u64) -> Solution {
+        self.statistics.insert(name.to_string(), value);
+        self
+    }
+
+    /// Adds the counters of another solution to the counters of this solution.
+    pub fn add_statistics(&mut self, statistics: &BTreeMap<String, u64>) {
+        for (name, value) in statistics {
+            *self.statistics.entry(name.clone()).or_insert(0) += *value;
+        }
+    }
+}
+
+/// The statistics of a solver that was cancelled before it solved the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedOut {
+    pub statistics: BTreeMap<String, u64>
+}
+
+impl TimedOut {
+    /// Adds the counters of a solution that was found before the solver was cancelled.
+    pub fn add_statistics(mut self, statistics: &BTreeMap<String, u64>) -> TimedOut {
+        for (name, value) in statistics {
+            *self.statistics.entry(name.clone()).or_insert(0) += *value;
+        }
+        self
+    }
+}
+
+pub trait Solver {
+    /// Solves the game, of which every node must have a successor.
+    fn solve(&self, game: &Game) -> Solution;
+
+    /// Solves the game unless the token is cancelled first, in which case the statistics so far are returned.
+    ///
+    /// Solvers that do not check the token ignore it and always solve the game.
+    fn solve_cancellable(&self, game: &Game, _cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        Ok(self.solve(game))
+    }
+}
+
+/// Returns the solution of a solver whose token is never cancelled.
+fn uncancelled(result: Result<Solution, TimedOut>) -> Solution {
+    match result {
+        Ok(solution) => solution,
+        Err(_)       => unreachable!("a solver cannot time out if it is never cancelled"),
+    }
+}
+
+/// Returns the statistics of a solver that was cancelled, given as pairs of their description and value.
+fn timed_out_with(statistics: &[(&str, u64)]) -> TimedOut {
+    TimedOut {
+        statistics: statistics.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+    }
+}
+
+pub struct SmallProgressMeasures {
+    /// The name of the strategy in which the nodes are lifted, see `strategies::NAMES`.
+    pub strategy: String,
+    pub prog_sort: ProgSort,
+    /// The player whose progress measures are computed, or `None` to compute those of both players.
+    pub player: Option<Owner>,
+    pub worklist: bool,
+    /// The number of threads that lift the nodes, which share a worklist if there is more than one. The measures of
+    /// both players are always computed by a single thread.
+    pub threads: usize,
+    /// Whether the strategy of the opponent of a one-sided progress measure is computed, see `Options::strategies`.
+    pub strategies: bool
+}
+
+impl Solver for SmallProgressMeasures {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let strategy = strategies::by_name(&self.strategy, game).unwrap();
+        let spm = if self.worklist {
+            algorithm::small_progress_measures_worklist
+        } else {
+            algorithm::small_progress_measures
+        };
+        let result = match self.player {
+            Some(player) if self.threads > 1 => {
+                let threads = self.threads;
+                algorithm::small_progress_measures_parallel(game, &*strategy, self.prog_sort, player, threads, cancel)
+            },
+            Some(player) => spm(game, &*strategy, self.prog_sort, player, cancel),
+            None         => algorithm::small_progress_measures_two_sided(game, &*strategy, self.prog_sort, cancel),
+        };
+
+        match result {
+            Ok(mut result) => {
+                // The measures of both players yield both strategies, the measure of one player only its own.
+                match self.player {
+                    Some(Owner::Even) if self.strategies => {
+                        result.strategy_odd = algorithm::subgame_strategy(game, Owner::Odd, &result.won_odd);
+                    },
+                    Some(Owner::Odd) if self.strategies  => {
+                        result.strategy_even = algorithm::subgame_strategy(game, Owner::Even, &result.won_even);
+                    },
+                    _                                    => {},
+                }
+
+                Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+                    .with("Number of iterations", result.nr_of_iterations)
+                    .with("Number of sub-iterations", result.nr_of_subiterations)
+                    .with("Number of prog steps", result.nr_of_steps))
+            },
+            Err(timed_out) => Err(timed_out_with(&[
+                ("Number of iterations", timed_out.nr_of_iterations),
+                ("Number of sub-iterations", timed_out.nr_of_subiterations),
+                ("Number of prog steps", timed_out.nr_of_steps)
+            ])),
+        }
+    }
+}
+
+pub struct Zielonka {
+    /// The number of threads that compute attractors and solve independent subgames.
+    pub threads: usize
+}
+
+impl Solver for Zielonka {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let result = zielonka::zielonka_parallel(game, self.threads, cancel).map_err(|timed_out| timed_out_with(&[
+            ("Number of recursions", timed_out.nr_of_recursions),
+            ("Number of attractors", timed_out.nr_of_attractors)
+        ]))?;
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+            .with("Number of recursions", result.nr_of_recursions)
+            .with("Maximal recursion depth", result.max_recursion_depth)
+            .with("Number of attractors", result.nr_of_attractors)
+            .with("Total size of attractors", result.total_attractor_size)
+            .with("Maximal size of an attractor", result.max_attractor_size)
+            .with("Number of subgames solved in parallel", result.nr_of_parallel_subgames))
+    }
+}
+
+pub struct PriorityPromotion;
+
+impl Solver for PriorityPromotion {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let result = priority_promotion::priority_promotion(game, cancel).map_err(|timed_out| timed_out_with(&[
+            ("Number of promotions", timed_out.nr_of_promotions),
+            ("Number of resets", timed_out.nr_of_resets)
+        ]))?;
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+            .with("Number of promotions", result.nr_of_promotions)
+            .with("Number of resets", result.nr_of_resets))
+    }
+}
+
+pub struct StrategyImprovement {
+    pub rule: ImprovementRule
+}
+
+impl Solver for StrategyImprovement {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let result = strategy_improvement::strategy_improvement(game, self.rule, cancel)
+            .map_err(|timed_out| timed_out_with(&[("Number of improvement steps", timed_out.nr_of_improvements)]))?;
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+            .with("Number of improvement steps", result.nr_of_improvements))
+    }
+}
+
+pub struct TangleLearning;
+
+impl Solver for TangleLearning {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let result = tangle_learning::tangle_learning(game, cancel).map_err(|timed_out| timed_out_with(&[
+            ("Number of iterations", timed_out.nr_of_iterations),
+            ("Number of tangles", timed_out.nr_of_tangles)
+        ]))?;
+        let nr_of_tangles = result.tangle_sizes.len() as u64;
+        let total_tangle_size = result.tangle_sizes.iter().sum::<usize>() as u64;
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+            .with("Number of iterations", result.nr_of_iterations)
+            .with("Number of tangles", nr_of_tangles)
+            .with("Total size of tangles", total_tangle_size))
+    }
+}
+
+pub struct SuccinctProgressMeasures {
+    /// The name of the strategy in which the nodes are lifted, see `strategies::NAMES`.
+    pub strategy: String,
+    /// Whether the strategy of `Odd` is computed, see `Options::strategies`.
+    pub strategies: bool
+}
+
+impl Solver for SuccinctProgressMeasures {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let strategy = strategies::by_name(&self.strategy, game).unwrap();
+        let result = succinct::succinct_progress_measures(game, &*strategy, cancel)
+            .map_err(|timed_out| timed_out_with(&[
+                ("Number of iterations", timed_out.nr_of_iterations),
+                ("Number of sub-iterations", timed_out.nr_of_subiterations)
+            ]))?;
+        let strategy_odd = if self.strategies {
+            algorithm::subgame_strategy(game, Owner::Odd, &result.won_odd)
+        } else {
+            PositionalStrategy::new()
+        };
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, strategy_odd)
+            .with("Number of iterations", result.nr_of_iterations)
+            .with("Number of sub-iterations", result.nr_of_subiterations))
+    }
+}
+
+pub struct FixpointIteration {
+    pub reset_sort: ResetSort
+}
+
+impl Solver for FixpointIteration {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        let result = fixpoint::fixpoint_iteration(game, self.reset_sort, cancel).map_err(|timed_out| timed_out_with(&[
+            ("Number of iterations", timed_out.nr_of_iterations),
+            ("Number of resets", timed_out.nr_of_resets)
+        ]))?;
+
+        Ok(Solution::new(result.won_even, result.won_odd, result.strategy_even, result.strategy_odd)
+            .with("Number of iterations", result.nr_of_iterations)
+            .with("Number of resets", result.nr_of_resets))
+    }
+}
+
+/// Solves the game one strongly connected component at a time with the inner solver, see `scc::solve_bottom_up`.
+pub struct Decomposition {
+    pub inner: Box<dyn Solver>
+}
+
+impl Solver for Decomposition {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        scc::solve_bottom_up(game, &*self.inner, cancel)
+    }
+}
+
+/// Preprocesses the game and solves what remains with the inner solver, see `preprocess::solve_preprocessed`.
+pub struct Preprocessing {
+    pub inner: Box<dyn Solver>
+}
+
+impl Solver for Preprocessing {
+    fn solve(&self, game: &Game) -> Solution {
+        uncancelled(self.solve_cancellable(game, &Cancellation::new()))
+    }
+
+    fn solve_cancellable(&self, game: &Game, cancel: &Cancellation) -> Result<Solution, TimedOut> {
+        preprocess::solve_preprocessed(game, &*self.inner, cancel)
+    }
+}
+
+/// The options of the solvers that are not part of their name.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The name of the strategy in which the progress measure solvers lift the nodes, see `strategies::NAMES`.
+    pub strategy: String,
+    pub prog_sort: ProgSort,
+    /// The player whose progress measures are computed, or `None` to compute those of both players.
+    pub player: Option<Owner>,
+    pub worklist: bool,
+    /// The number of threads of the progress measure solvers and Zielonka's algorithm.
+    pub threads: usize,
+    pub rule: ImprovementRule,
+    pub reset_sort: ResetSort,
+    /// Whether the solutions contain the strategies of both players. Otherwise the strategies that can only be
+    /// obtained by solving a winning set again are left empty, which is enough to decide the winner of a node.
+    pub strategies: bool
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            strategy: "input".to_string(),
+            prog_sort: ProgSort::ClosedForm,
+            player: Some(Owner::Even),
+            worklist: false,
+            threads: 1,
+            rule: ImprovementRule::AllSwitches,
+            reset_sort: ResetSort::All,
+            strategies: true
+        }
+    }
+}
+
+/// The names of the solvers, by which `by_name` selects them.
+pub const NAMES: &[&str] = &["spm", "zielonka", "pp", "si", "tl", "succinct", "fpi"];
+
+/// Returns `true` if the solver with the provided name lifts the nodes in the order of a strategy.
+pub fn needs_strategy(name: &str) -> bool {
+    name == "spm" || name == "succinct"
+}
+
+/// Returns the solver with the provided name and options, or `None` if there is no solver with that name or the
+/// options name a strategy that does not exist.
+pub fn by_name(name: &str, options: &Options) -> Option<Box<dyn Solver>> {
+    if needs_strategy(name) && !strategies::NAMES.contains(&options.strategy.as_str()) {
+        return None;
+    }
+
+    match name {
+        "spm"      => Some(Box::new(SmallProgressMeasures {
+            strategy: options.strategy.clone(),
+            prog_sort: options.prog_sort,
+            player: options.player,
+            worklist: options.worklist,
+            threads: options.threads,
+            strategies: options.strategies
+        })),
+        "zielonka" => Some(Box::new(Zielonka { threads: options.threads })),
+        "pp"       => Some(Box::new(PriorityPromotion)),
+        "si"       => Some(Box::new(StrategyImprovement { rule: options.rule })),
+        "tl"       => Some(Box::new(TangleLearning)),
+        "succinct" => Some(Box::new(SuccinctProgressMeasures {
+            strategy: options.strategy.clone(),
+            strategies: options.strategies
+        })),
+        "fpi"      => Some(Box::new(FixpointIteration { reset_sort: options.reset_sort })),
+        _          => None,
+    }
+}
@@ -1,39 +1,52 @@
 extern crate rand;
 use self::rand::Rng;
 use std::collections::HashSet;
-use std::collections::HashMap;
 use pg::*;
-use std::borrow::BorrowMut;
 use std::iter::Iterator;
-use std::collections::LinkedList;
 use std::collections::VecDeque;
 
 pub trait Strategy {
-    fn vertex(&self) -> Vec<&Node>;
+    /// Returns the indices of the nodes of the game in the order in which they should be lifted.
+    fn vertex(&self) -> Vec<u32>;
 }
 
-pub struct InputStrategy<'game> (Vec<&'game Node>);
+/// The names of the strategies, by which `by_name` selects them.
+pub const NAMES: &[&str] = &["random", "input", "priority", "succesor", "selfloop"];
+
+/// Returns the strategy with the provided name for the game, or `None` if there is no strategy with that name.
+pub fn by_name<'game>(name: &str, game: &'game Game) -> Option<Box<dyn Strategy + 'game>> {
+    match name {
+        "random"   => Some(Box::new(RandomStrategy::new(game))),
+        "input"    => Some(Box::new(InputStrategy::new(game))),
+        "priority" => Some(Box::new(PriorityStrategy::new(game))),
+        "succesor" => Some(Box::new(SuccesorStrategy::new(game))),
+        "selfloop" => Some(Box::new(SelfLoopStrategy::new(game))),
+        _          => None,
+    }
+}
+
+pub struct InputStrategy<'game> (&'game Game);
 impl<'game> InputStrategy<'game> {
     pub fn new(game: &'game Game) -> InputStrategy<'game> {
-        return InputStrategy(game.nodes().into_iter().collect())
+        return InputStrategy(game)
     }
 }
-pub struct RandomStrategy<'game> (Vec<&'game Node>);
+pub struct RandomStrategy<'game> (&'game Game);
 impl<'game> RandomStrategy<'game> {
     pub fn new(game: &'game Game) -> RandomStrategy<'game> {
-        return RandomStrategy(game.nodes().into_iter().collect());
+        return RandomStrategy(game);
     }
 }
-pub struct PriorityStrategy<'game> (Vec<&'game Node>);
+pub struct PriorityStrategy<'game> (&'game Game);
 impl<'game> PriorityStrategy<'game> {
     pub fn new(game: &'game Game) -> PriorityStrategy<'game> {
-        return PriorityStrategy(game.nodes().into_iter().collect());
+        return PriorityStrategy(game);
     }
 }
-pub struct SuccesorStrategy<'game> (Vec<&'game Node>);
+pub struct SuccesorStrategy<'game> (&'game Game);
 impl<'game> SuccesorStrategy<'game> {
     pub fn new(game: &'game Game) -> SuccesorStrategy<'game> {
-        return SuccesorStrategy(game.nodes().into_iter().collect());
+        return SuccesorStrategy(game);
     }
 }
 
@@ -71,88 +84,66 @@ fn lowest_element<'game>(nodes: &HashSet<&'game Node>) -> &'game Node {
     }).unwrap()
 }
 
-pub struct SelfLoopStrategy<'game> (HashMap<&'game Node, u32>);
+pub struct SelfLoopStrategy<'game> (&'game Game, Vec<u32>);
 impl<'game> SelfLoopStrategy<'game> {
     pub fn new(game: &'game Game) -> SelfLoopStrategy<'game> {
-        let nodes = game.nodes();
-
-        let iter = &nodes.clone();
-        let endings = iter.iter().filter(|x| 
-            x.succ.contains(&x.id) && (x.succ.len() == 1 || 
-                (x.prio % 2 == 0 && x.owner == Owner::Even) ||
-                (x.prio % 2 == 1 && x.owner == Owner::Odd)
-            )
-        );
-        let mut reversed: HashMap<&u32, LinkedList<u32>> = HashMap::new();
-        for v in iter {
-            for e in &v.succ {
-                let mut r = reversed.entry(e).or_insert(LinkedList::new());
-                r.push_back(v.id);
-            }
-        }
-        let mut queue = &mut VecDeque::new();
-        let mut dist = &mut HashMap::new();
-        for v in nodes {
-            dist.insert(v, u32::max_value());
-        }
+        let endings = game.nodes().filter(|&x| {
+            let succ = game.succ(x);
+            succ.contains(&x) && (succ.len() == 1 || Owner::of_prio(game.prio(x)) == game.owner(x))
+        });
+        let mut queue = VecDeque::new();
+        let mut dist = vec![u32::MAX; game.len()];
         for v in endings {
-            dist.insert(v, 0);
-            queue.push_back(*v);
+            dist[v as usize] = 0;
+            queue.push_back(v);
         }
-        
-        while queue.len() > 0 {
-            let v = queue.pop_front().unwrap();
-            let d_v = * dist.get(v).unwrap();
-            for w_id in reversed.get(&v.id).unwrap_or(&LinkedList::new()) {
-                let w = game.node(w_id);
-                let d_w = *dist.get(w).unwrap();
-                if d_w > d_v+1 {
-                    dist.insert(w, d_v+1);
+
+        while let Some(v) = queue.pop_front() {
+            let d_v = dist[v as usize];
+            for &w in game.pred(v) {
+                if dist[w as usize] > d_v + 1 {
+                    dist[w as usize] = d_v + 1;
                     queue.push_back(w);
                 }
             }
         }
-        // let lowest = lowest_element(&nodes);
-        return SelfLoopStrategy(dist.clone());
+        return SelfLoopStrategy(game, dist);
     }
 }
 
 // pub
 impl<'game> Strategy for SelfLoopStrategy<'game> {
-    fn vertex(&self) -> Vec<&Node> {
-        let mut v: Vec<_> = self.0.iter().collect();
-        v.sort_by(|x, y| x.1.cmp(&y.1));
-        return v.iter().map(|x| *x.0).collect();
+    fn vertex(&self) -> Vec<u32> {
+        let mut v: Vec<_> = self.0.nodes().collect();
+        v.sort_by_key(|x| self.1[*x as usize]);
+        return v;
     }
 }
 impl<'game> Strategy for InputStrategy<'game> {
-    fn vertex(&self) -> Vec<&Node> {
-        let mut v = self.0.clone();
-        v.sort_by(|x, y| x.count.cmp(&y.count));
-        return v;
+    fn vertex(&self) -> Vec<u32> {
+        // The nodes are indexed in the order in which they were specified.
+        return self.0.nodes().collect();
     }
 }
 impl<'game> Strategy for PriorityStrategy<'game> {
-    fn vertex(&self) -> Vec<&Node> {
-        let mut v = self.0.clone();
-        v.sort_by(|x, y| x.prio.cmp(&y.prio));
+    fn vertex(&self) -> Vec<u32> {
+        let mut v: Vec<_> = self.0.nodes().collect();
+        v.sort_by_key(|x| self.0.prio(*x));
         return v;
     }
 }
 impl<'game> Strategy for RandomStrategy<'game> {
-    fn vertex(&self) -> Vec<&Node> {
-        let mut clone = self.0.clone();
-        let mut v = clone.borrow_mut();
-        rand::thread_rng().shuffle(v);
-        return v.to_vec();
+    fn vertex(&self) -> Vec<u32> {
+        let mut v: Vec<_> = self.0.nodes().collect();
+        rand::thread_rng().shuffle(&mut v);
+        return v;
     }
 }
 
 impl<'game> Strategy for SuccesorStrategy<'game> {
-    fn vertex(&self) -> Vec<&Node> {
-        let mut v = self.0.clone();
-        v.sort_by(|x, y| x.succ.len().cmp(&y.succ.len()));
+    fn vertex(&self) -> Vec<u32> {
+        let mut v: Vec<_> = self.0.nodes().collect();
+        v.sort_by_key(|x| self.0.succ(*x).len());
         return v;
     }
 }
-
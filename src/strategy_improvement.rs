@@ -0,0 +1,411 @@
+extern crate rand;
+use self::rand::Rng;
+
+use std::cmp;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::slice;
+
+use pg::*;
+use cancel::Cancellation;
+
+/// How a strategy of `Even` is improved as long as it has improving switches.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ImprovementRule {
+    /// Switch every node that can be improved to its best successor at once.
+    AllSwitches,
+    /// Switch only the first node that can be improved to its best successor.
+    SingleSwitch,
+    /// Optimise without a random edge first, and only switch to that edge afterwards if it improves.
+    RandomFacet
+}
+
+pub struct StrategyImprovementResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// The number of times the strategy of `Even` was improved.
+    pub nr_of_improvements: u64
+}
+
+/// The statistics of strategy improvement that was cancelled before it solved the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StrategyImprovementTimedOut {
+    pub nr_of_improvements: u64
+}
+
+/// The outcome of the play from a node if `Even` follows its strategy and `Odd` plays the best counter-strategy.
+///
+/// The play ends in a cycle on which `cycle` is the most relevant node. Before reaching it, the play passes the
+/// nodes more relevant than `cycle`, of which the ranks are in `profile` from most to least relevant, in `dist` steps.
+#[derive(Debug, Clone)]
+struct Valuation {
+    cycle: u32,
+    profile: Vec<u32>,
+    dist: u32
+}
+
+/// Assigns every node a unique rank with the same parity as its priority.
+///
+/// A lower priority is more significant, so it is given a higher rank. This does not change who wins any cycle.
+fn ranks(game: &Game) -> Vec<u32> {
+    let mut nodes = game.nodes().collect::<Vec<u32>>();
+    nodes.sort_by_key(|v| cmp::Reverse(game.prio(*v)));
+
+    let mut ranks = vec![0; game.len()];
+    let mut next = 0;
+
+    for v in nodes {
+        if next % 2 != game.prio(v) % 2 {
+            next += 1;
+        }
+
+        ranks[v as usize] = next;
+        next += 1;
+    }
+
+    ranks
+}
+
+/// How good a cycle with the rank as its most relevant rank is for `Even`.
+fn reward(rank: u32) -> i64 {
+    if rank % 2 == 1 {
+        -(rank as i64)
+    } else {
+        rank as i64
+    }
+}
+
+/// Compares the nodes passed by two plays from the point of view of `Even`.
+///
+/// The most relevant rank that only one of the plays passes decides, which is good for `Even` if it is even.
+fn compare_profiles(a: &[u32], b: &[u32]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+
+    loop {
+        let (rank, ordering) = match (a.get(i), b.get(j)) {
+            (None, None)                 => return Ordering::Equal,
+            (Some(x), None)              => (*x, Ordering::Greater),
+            (None, Some(y))              => (*y, Ordering::Less),
+            (Some(x), Some(y)) if x == y => {
+                i += 1;
+                j += 1;
+                continue;
+            },
+            (Some(x), Some(y)) if x > y  => (*x, Ordering::Greater),
+            (Some(_), Some(y))           => (*y, Ordering::Less),
+        };
+
+        // The ordering is correct if the deciding play passes the rank.
+        return if Owner::of_prio(rank) == Owner::Even { ordering } else { ordering.reverse() };
+    }
+}
+
+/// Compares two plays ending in the same cycle from the point of view of `Even`.
+///
+/// If they pass the same nodes, a shorter play is better if the cycle is won by `Even` and a longer play otherwise.
+fn compare_paths(ranks: &[u32], a: &Valuation, b: &Valuation) -> Ordering {
+    compare_profiles(&a.profile, &b.profile).then_with(|| {
+        if Owner::of_prio(ranks[a.cycle as usize]) == Owner::Even {
+            b.dist.cmp(&a.dist)
+        } else {
+            a.dist.cmp(&b.dist)
+        }
+    })
+}
+
+/// Compares two valuations from the point of view of `Even`.
+fn compare(ranks: &[u32], a: &Valuation, b: &Valuation) -> Ordering {
+    if a.cycle != b.cycle {
+        return reward(ranks[a.cycle as usize]).cmp(&reward(ranks[b.cycle as usize]));
+    }
+
+    compare_paths(ranks, a, b)
+}
+
+/// The successors of the node if `Even` follows its strategy.
+fn successors<'a>(game: &'a Game, strategy: &'a [u32], v: u32) -> &'a [u32] {
+    match game.owner(v) {
+        Owner::Even => slice::from_ref(&strategy[v as usize]),
+        Owner::Odd  => game.succ(v),
+    }
+}
+
+/// Checks whether the node lies on a cycle of nodes without a valuation that are at most as relevant.
+fn on_cycle(game: &Game, ranks: &[u32], strategy: &[u32], valuation: &[Option<Valuation>], u: u32) -> bool {
+    let mut visited = HashSet::new();
+    let mut queue = successors(game, strategy, u).iter().cloned().collect::<VecDeque<u32>>();
+
+    while let Some(v) = queue.pop_front() {
+        if v == u {
+            return true;
+        }
+
+        if valuation[v as usize].is_some() || ranks[v as usize] > ranks[u as usize] || !visited.insert(v) {
+            continue;
+        }
+
+        queue.extend(successors(game, strategy, v));
+    }
+
+    false
+}
+
+/// Computes the nodes without a valuation from which the provided node can be reached if `Even` follows its strategy.
+fn reaching(game: &Game, strategy: &[u32], valuation: &[Option<Valuation>], u: u32) -> HashSet<u32> {
+    let mut nodes = HashSet::new();
+    let mut queue = VecDeque::new();
+    nodes.insert(u);
+    queue.push_back(u);
+
+    while let Some(w) = queue.pop_front() {
+        for &v in game.pred(w) {
+            if valuation[v as usize].is_some() || nodes.contains(&v) {
+                continue;
+            }
+
+            if game.owner(v) == Owner::Odd || strategy[v as usize] == w {
+                nodes.insert(v);
+                queue.push_back(v);
+            }
+        }
+    }
+
+    nodes
+}
+
+/// Computes the best play for `Odd` from every node towards the cycle through `u`.
+///
+/// Every cycle avoiding `u` is at least as good for `Even` as not taking it, so the least valuations are found by
+/// repeatedly relaxing them as in the algorithm of Bellman-Ford.
+fn paths(game: &Game, ranks: &[u32], strategy: &[u32], nodes: &HashSet<u32>, u: u32, valuation: &mut [Option<Valuation>]) {
+    let mut paths = vec![None; game.len()];
+    paths[u as usize] = Some(Valuation {
+        cycle: u,
+        profile: Vec::new(),
+        dist: 0
+    });
+
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for &v in nodes {
+            if v == u {
+                continue;
+            }
+
+            let best = successors(game, strategy, v)
+                .iter()
+                .filter(|w| nodes.contains(w))
+                .filter_map(|w| paths[*w as usize].as_ref())
+                .min_by(|a, b| compare_paths(ranks, a, b))
+                .cloned();
+
+            let mut path: Valuation = match best {
+                Some(path) => path,
+                None       => continue,
+            };
+
+            if ranks[v as usize] > ranks[u as usize] {
+                let i = path.profile.iter().take_while(|x| **x > ranks[v as usize]).count();
+                path.profile.insert(i, ranks[v as usize]);
+            }
+            path.dist += 1;
+
+            let improves = match paths[v as usize] {
+                Some(ref current) => compare_paths(ranks, &path, current) == Ordering::Less,
+                None              => true,
+            };
+
+            if improves {
+                paths[v as usize] = Some(path);
+                changed = true;
+            }
+        }
+    }
+
+    for &v in nodes {
+        valuation[v as usize] = paths[v as usize].take();
+    }
+}
+
+/// Computes the valuation of every node if `Even` follows its strategy and `Odd` plays the best counter-strategy.
+///
+/// The cycles are considered from the worst to the best for `Even`. A node that can reach a cycle through the node
+/// with only less relevant nodes, is valued with the best play to that cycle for `Odd`.
+fn valuation(game: &Game, ranks: &[u32], strategy: &[u32]) -> Vec<Valuation> {
+    let mut order = game.nodes().collect::<Vec<u32>>();
+    order.sort_by_key(|u| reward(ranks[*u as usize]));
+
+    let mut valuation = vec![None; game.len()];
+
+    for u in order {
+        if valuation[u as usize].is_some() || !on_cycle(game, ranks, strategy, &valuation, u) {
+            continue;
+        }
+
+        let nodes = reaching(game, strategy, &valuation, u);
+        paths(game, ranks, strategy, &nodes, u, &mut valuation);
+    }
+
+    valuation
+        .into_iter()
+        .map(|val| val.expect("Every node reaches a cycle."))
+        .collect()
+}
+
+/// Returns the best successor of the node if it is better than the current choice of the strategy.
+fn improving_switch(game: &Game, ranks: &[u32], strategy: &[u32], valuation: &[Valuation], v: u32) -> Option<u32> {
+    let best = *game.succ(v)
+        .iter()
+        .max_by(|a, b| compare(ranks, &valuation[**a as usize], &valuation[**b as usize]))
+        .unwrap();
+    let current = strategy[v as usize];
+
+    if compare(ranks, &valuation[best as usize], &valuation[current as usize]) == Ordering::Greater {
+        Some(best)
+    } else {
+        None
+    }
+}
+
+/// Improves the strategy with the random facet rule, only using the allowed edges of `Even`.
+///
+/// The rule optimises the strategy without a random edge that it does not use, and then only switches to that edge and
+/// optimises again with all edges if the edge improves. As this recurses once for every edge that is left out, the
+/// recursion is unfolded into a stack of the edges that are left out by the calls waiting for their optimisation.
+/// The token is checked before every edge is left out or tried, which returns `None` once it is cancelled.
+fn random_facet(game: &Game,
+                ranks: &[u32],
+                mut strategy: Vec<u32>,
+                allowed: &[(u32, u32)],
+                cancel: &Cancellation,
+                nr_of_improvements: &mut u64) -> Option<Vec<u32>> {
+    let mut rng = rand::thread_rng();
+    let mut left_out = vec![false; allowed.len()];
+    let mut stack = Vec::new();
+
+    loop {
+        if cancel.is_cancelled() {
+            return None;
+        }
+
+        let facets = (0..allowed.len())
+            .filter(|i| !left_out[*i] && strategy[allowed[*i].0 as usize] != allowed[*i].1)
+            .collect::<Vec<usize>>();
+
+        if !facets.is_empty() {
+            let i = facets[rng.gen_range(0, facets.len())];
+            left_out[i] = true;
+            stack.push(i);
+            continue;
+        }
+
+        // The strategy is optimal without the edges on the stack, so the last call that left out an edge continues.
+        loop {
+            let i = match stack.pop() {
+                Some(i) => i,
+                None    => return Some(strategy),
+            };
+            left_out[i] = false;
+
+            let (v, w) = allowed[i];
+            let valuation = valuation(game, ranks, &strategy);
+            let current = strategy[v as usize];
+            if compare(ranks, &valuation[w as usize], &valuation[current as usize]) == Ordering::Greater {
+                strategy[v as usize] = w;
+                *nr_of_improvements += 1;
+                break;
+            }
+        }
+    }
+}
+
+/// Solves the game using the discrete strategy improvement algorithm of Vöge and Jurdziński.
+///
+/// Starting from an arbitrary strategy of `Even`, every iteration computes the valuation of the strategy against the
+/// best counter-strategy of `Odd`, and improves the strategy using the rule until that is no longer possible. The nodes
+/// that then end in a cycle won by `Even` are won by `Even`. As the strategy of `Even` is then optimal, the best
+/// counter-strategy of `Odd`, which moves to the successor with the least valuation, is winning for `Odd`. Every node
+/// in the game must have a successor. The token is checked before every improvement. Once it is cancelled, the
+/// statistics so far are returned instead.
+pub fn strategy_improvement(game: &Game,
+                            rule: ImprovementRule,
+                            cancel: &Cancellation) -> Result<StrategyImprovementResult, StrategyImprovementTimedOut> {
+    let ranks = ranks(game);
+    let mut strategy = game.nodes().map(|v| game.succ(v)[0]).collect::<Vec<u32>>();
+    let mut nr_of_improvements = 0;
+
+    let valuation = match rule {
+        ImprovementRule::AllSwitches | ImprovementRule::SingleSwitch => loop {
+            if cancel.is_cancelled() {
+                return Err(StrategyImprovementTimedOut { nr_of_improvements });
+            }
+
+            let valuation = valuation(game, &ranks, &strategy);
+            let switches = game.nodes()
+                .filter(|v| game.owner(*v) == Owner::Even)
+                .filter_map(|v| improving_switch(game, &ranks, &strategy, &valuation, v).map(|w| (v, w)));
+
+            let switches = if rule == ImprovementRule::AllSwitches {
+                switches.collect::<Vec<_>>()
+            } else {
+                switches.take(1).collect::<Vec<_>>()
+            };
+
+            if switches.is_empty() {
+                break valuation;
+            }
+
+            for (v, w) in switches {
+                strategy[v as usize] = w;
+            }
+            nr_of_improvements += 1;
+        },
+        ImprovementRule::RandomFacet => {
+            let allowed = game.nodes()
+                .filter(|v| game.owner(*v) == Owner::Even)
+                .flat_map(|v| game.succ(v).iter().map(move |w| (v, *w)))
+                .collect::<Vec<_>>();
+            strategy = match random_facet(game, &ranks, strategy, &allowed, cancel, &mut nr_of_improvements) {
+                Some(strategy) => strategy,
+                None           => return Err(StrategyImprovementTimedOut { nr_of_improvements }),
+            };
+
+            valuation(game, &ranks, &strategy)
+        }
+    };
+
+    let (won_even, won_odd): (HashSet<u32>, HashSet<u32>) = game
+        .nodes()
+        .partition(|v| Owner::of_prio(ranks[valuation[*v as usize].cycle as usize]) == Owner::Even);
+
+    let strategy_even = PositionalStrategy(won_even
+        .iter()
+        .filter(|v| game.owner(**v) == Owner::Even)
+        .map(|v| (*v, strategy[*v as usize]))
+        .collect());
+    let strategy_odd = PositionalStrategy(won_odd
+        .iter()
+        .filter(|v| game.owner(**v) == Owner::Odd)
+        .map(|v| {
+            let w = *game.succ(*v)
+                .iter()
+                .min_by(|a, b| compare(&ranks, &valuation[**a as usize], &valuation[**b as usize]))
+                .unwrap();
+            (*v, w)
+        })
+        .collect());
+
+    Ok(StrategyImprovementResult {
+        won_even,
+        won_odd,
+        strategy_even,
+        strategy_odd,
+        nr_of_improvements
+    })
+}
@@ -0,0 +1,264 @@
+use std::cmp;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+use strategies::Strategy;
+
+/// A succinct measure, which has a binary string for every odd priority, starting at the most significant priority.
+///
+/// The strings are ordered by `0x < ε < 1x`, so every string lies between those that extend it with a 0 or with a 1.
+/// The total length of the strings is bounded by the logarithm of the number of nodes, as by Jurdziński and Lazić.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuccinctMeasure(pub Vec<Vec<bool>>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuccinctMeasureT {
+    Top,
+    Measure(SuccinctMeasure)
+}
+
+/// Compares two binary strings by the order `0x < ε < 1x`.
+fn compare_strings(a: &[bool], b: &[bool]) -> Ordering {
+    for i in 0.. {
+        match (a.get(i), b.get(i)) {
+            (None, None)                 => return Ordering::Equal,
+            (Some(x), None)              => return if *x { Ordering::Greater } else { Ordering::Less },
+            (None, Some(y))              => return if *y { Ordering::Less } else { Ordering::Greater },
+            (Some(x), Some(y)) if x != y => return x.cmp(y),
+            _                            => {},
+        }
+    }
+
+    unreachable!()
+}
+
+impl Ord for SuccinctMeasure {
+    fn cmp(&self, other: &SuccinctMeasure) -> Ordering {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .map(|(a, b)| compare_strings(a, b))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for SuccinctMeasure {
+    fn partial_cmp(&self, other: &SuccinctMeasure) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `Ord` is implemented by hand, so `Top` is greater than every measure.
+impl Ord for SuccinctMeasureT {
+    fn cmp(&self, other: &SuccinctMeasureT) -> Ordering {
+        match (self, other) {
+            (SuccinctMeasureT::Top, SuccinctMeasureT::Top)               => Ordering::Equal,
+            (SuccinctMeasureT::Top, _)                                   => Ordering::Greater,
+            (_, SuccinctMeasureT::Top)                                   => Ordering::Less,
+            (SuccinctMeasureT::Measure(a), SuccinctMeasureT::Measure(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for SuccinctMeasureT {
+    fn partial_cmp(&self, other: &SuccinctMeasureT) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The shape of the succinct measures of a game.
+struct Bounds {
+    /// The number of odd priorities up to and including the maximal priority.
+    length: usize,
+    /// The maximal total length of the strings.
+    bits: usize
+}
+
+impl Bounds {
+    fn new(game: &Game) -> Bounds {
+        let mut bits = 0;
+        while (1 << bits) < game.len() {
+            bits += 1;
+        }
+
+        Bounds {
+            length: (game.max_prio() as usize).div_ceil(2),
+            bits
+        }
+    }
+
+    /// Returns the number of strings up to and including the specified priority.
+    fn index(&self, prio: u32) -> usize {
+        cmp::min((prio as usize).div_ceil(2), self.length)
+    }
+
+    /// Returns the least measure that starts with the provided strings.
+    ///
+    /// The next string consists of as many zeroes as the remaining length allows, and the strings after it are empty.
+    fn least(&self, mut strings: Vec<Vec<bool>>) -> SuccinctMeasure {
+        let used = strings.iter().map(|s| s.len()).sum::<usize>();
+
+        if strings.len() < self.length {
+            strings.push(vec![false; self.bits - used]);
+        }
+        while strings.len() < self.length {
+            strings.push(Vec::new());
+        }
+
+        SuccinctMeasure(strings)
+    }
+
+    fn new_measure(&self) -> SuccinctMeasureT {
+        SuccinctMeasureT::Measure(self.least(Vec::new()))
+    }
+}
+
+/// Returns the least string that is greater than the provided string and at most as long as the maximal length.
+fn next_string(s: &[bool], max: usize) -> Option<Vec<bool>> {
+    let mut next = s.to_vec();
+
+    // Strings that extend this string with a 1 are greater, of which the one with the most zeroes after it is least.
+    if next.len() < max {
+        next.push(true);
+        while next.len() < max {
+            next.push(false);
+        }
+
+        return Some(next);
+    }
+
+    // Otherwise the least greater string is the first string shortened from a 0.
+    while next.last() == Some(&true) {
+        next.pop();
+    }
+
+    next.pop().map(|_| next)
+}
+
+// slide 22, with succinct measures
+fn prog(game: &Game, bounds: &Bounds, progress: &[SuccinctMeasureT], v: u32, w: u32) -> SuccinctMeasureT {
+    let m_w = match progress[w as usize] {
+        SuccinctMeasureT::Top            => return SuccinctMeasureT::Top,
+        SuccinctMeasureT::Measure(ref m) => m,
+    };
+
+    let v_prio = game.prio(v);
+    let i = bounds.index(v_prio);
+
+    if Owner::of_prio(v_prio) == Owner::Even {
+        return SuccinctMeasureT::Measure(bounds.least(m_w.0[..i].to_vec()));
+    }
+
+    // Increase the string of the least significant priority that can be increased, up to and including this priority.
+    for j in (0..i).rev() {
+        let used = m_w.0[..j].iter().map(|s| s.len()).sum::<usize>();
+
+        if let Some(next) = next_string(&m_w.0[j], bounds.bits - used) {
+            let mut strings = m_w.0[..j].to_vec();
+            strings.push(next);
+
+            return SuccinctMeasureT::Measure(bounds.least(strings));
+        }
+    }
+
+    SuccinctMeasureT::Top
+}
+
+/// Lifts the measure of the node in place and returns `true` if it increased.
+fn lift(game: &Game, bounds: &Bounds, v: u32, progress: &mut [SuccinctMeasureT]) -> bool {
+    let val = {
+        let edges = game.succ(v).iter().map(|w| prog(game, bounds, progress, v, *w));
+        if game.owner(v) == Owner::Even {
+            edges.min().unwrap()
+        } else {
+            edges.max().unwrap()
+        }
+    };
+    if val > progress[v as usize] {
+        progress[v as usize] = val;
+        return true;
+    }
+
+    false
+}
+
+pub struct SuccinctResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    /// The number of sweeps over all nodes.
+    pub nr_of_iterations: u64,
+    /// The number of attempts to lift a node.
+    pub nr_of_subiterations: u64
+}
+
+/// The statistics of succinct progress measures that were cancelled before the measures were stable.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SuccinctTimedOut {
+    pub nr_of_iterations: u64,
+    pub nr_of_subiterations: u64
+}
+
+/// Computes the progress measures of the game with succinct measures, of which the number is quasi-polynomial.
+///
+/// The nodes are lifted in the order of the strategy, as in `small_progress_measures`. The strategy of `Even` chooses
+/// the successor with the least `prog`. The strategy of `Odd` is not computed, as it can only be obtained by solving
+/// the subgame that `Odd` wins, see `algorithm::subgame_strategy`. The token is checked before every node is lifted.
+/// Once it is cancelled, the statistics so far are returned instead.
+pub fn succinct_progress_measures(game: &Game,
+                                  strategy: &dyn Strategy,
+                                  cancel: &Cancellation) -> Result<SuccinctResult, SuccinctTimedOut> {
+    let bounds = Bounds::new(game);
+    let mut progress = vec![bounds.new_measure(); game.len()];
+    let vertices = strategy.vertex();
+    let mut nr_of_iterations = 0;
+    let mut nr_of_subiterations = 0;
+
+    loop {
+        nr_of_iterations += 1;
+
+        let mut any_change = false;
+
+        for &v in &vertices {
+            if cancel.is_cancelled() {
+                return Err(SuccinctTimedOut { nr_of_iterations, nr_of_subiterations });
+            }
+
+            while lift(game, &bounds, v, &mut progress) {
+                nr_of_subiterations += 1;
+                any_change = true;
+            }
+            nr_of_subiterations += 1;
+        }
+
+        if !any_change {
+            break;
+        }
+    }
+
+    let (won_odd, won_even): (HashSet<u32>, HashSet<u32>) = game
+        .nodes()
+        .partition(|v| progress[*v as usize] == SuccinctMeasureT::Top);
+
+    let mut strategy_even = PositionalStrategy::new();
+    for &v in &won_even {
+        if game.owner(v) == Owner::Even {
+            let w = game.succ(v)
+                .iter()
+                .min_by_key(|w| prog(game, &bounds, &progress, v, **w))
+                .unwrap();
+            strategy_even.0.insert(v, *w);
+        }
+    }
+
+    Ok(SuccinctResult {
+        won_even,
+        won_odd,
+        strategy_even,
+        nr_of_iterations,
+        nr_of_subiterations
+    })
+}
@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+
+use pg::*;
+use cancel::Cancellation;
+use verify;
+
+pub struct TangleLearningResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    /// The number of searches for dominions over the remaining game.
+    pub nr_of_iterations: u64,
+    /// The number of nodes in every tangle that was learned, in the order in which they were found.
+    pub tangle_sizes: Vec<usize>
+}
+
+/// The statistics of tangle learning that was cancelled before it solved the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TangleLearningTimedOut {
+    pub nr_of_iterations: u64,
+    pub nr_of_tangles: u64
+}
+
+/// A set of nodes in which the player can keep every play and win it, unless the opponent leaves it.
+struct Tangle {
+    player: Owner,
+    nodes: HashSet<u32>,
+    strategy: PositionalStrategy,
+    /// The nodes outside the tangle the opponent can move to.
+    escapes: HashSet<u32>
+}
+
+/// The outcome of a search over the remaining game.
+enum Search {
+    /// A tangle the opponent cannot leave, which is won by its player.
+    Dominion(Tangle),
+    /// The tangles found while no dominion was found.
+    Tangles(Vec<Tangle>)
+}
+
+/// Computes the attractor of the player to the target within the subgame, which also attracts tangles.
+///
+/// A tangle of the player is attracted entirely if it lies within the subgame and every escape from it that lies within
+/// the subgame is already attracted. The player then follows the strategy of the tangle on its nodes.
+fn tangle_attractor(game: &Game,
+                    player: Owner,
+                    subgame: &HashSet<u32>,
+                    target: &HashSet<u32>,
+                    tangles: &[Tangle]) -> Attractor {
+    let mut a = game.attractor(player, subgame, target);
+
+    loop {
+        let mut attracted = HashSet::new();
+
+        for t in tangles {
+            if t.player != player || t.nodes.is_subset(&a.nodes) || !t.nodes.is_subset(subgame) {
+                continue;
+            }
+
+            if t.escapes.iter().all(|w| !subgame.contains(w) || a.nodes.contains(w)) {
+                for (v, w) in &t.strategy.0 {
+                    if !a.nodes.contains(v) {
+                        a.strategy.0.insert(*v, *w);
+                    }
+                }
+                attracted.extend(t.nodes.iter().cloned());
+            }
+        }
+
+        if attracted.is_empty() {
+            return a;
+        }
+
+        attracted.extend(a.nodes);
+        let b = game.attractor(player, subgame, &attracted);
+        a.strategy.0.extend(b.strategy.0);
+        a.nodes = b.nodes;
+    }
+}
+
+/// Extracts the tangles of the player from its region.
+///
+/// First the nodes are removed from which the opponent can leave the region within the subgame, or from which the
+/// player cannot stay in it. The tangles are the bottom strongly connected components of the remainder that contain
+/// a cycle. Since the opponent would have attracted any node that can move to a region of its own, the escapes from
+/// these tangles only lead to more significant regions of the player.
+fn extract_tangles(game: &Game,
+                   player: Owner,
+                   remaining: &HashSet<u32>,
+                   subgame: &HashSet<u32>,
+                   region: &HashSet<u32>,
+                   strategy: &PositionalStrategy) -> Vec<Tangle> {
+    let mut closed = region.clone();
+
+    loop {
+        let open = closed
+            .iter()
+            .filter(|v| if game.owner(**v) == player {
+                !strategy.successor(v).is_some_and(|w| closed.contains(w))
+            } else {
+                game.succ(**v).iter().any(|w| subgame.contains(w) && !closed.contains(w))
+            })
+            .cloned()
+            .collect::<Vec<u32>>();
+
+        if open.is_empty() {
+            break;
+        }
+
+        for v in open {
+            closed.remove(&v);
+        }
+    }
+
+    let graph = closed
+        .iter()
+        .map(|v| {
+            let succ = if game.owner(*v) == player {
+                vec![*strategy.successor(v).unwrap()]
+            } else {
+                game.succ(*v).iter().filter(|w| closed.contains(w)).cloned().collect()
+            };
+
+            (*v, succ)
+        })
+        .collect::<verify::Graph>();
+
+    let mut tangles = Vec::new();
+
+    for component in verify::sccs(&graph, &closed) {
+        let bottom = component.iter().all(|v| graph[v].iter().all(|w| component.contains(w)));
+        let cycle = component.len() > 1 || component.iter().all(|v| graph[v].contains(v));
+
+        if !bottom || !cycle {
+            continue;
+        }
+
+        let escapes = component
+            .iter()
+            .filter(|v| game.owner(**v) != player)
+            .flat_map(|v| game.succ(*v).iter())
+            .filter(|w| remaining.contains(w) && !component.contains(w))
+            .cloned()
+            .collect();
+        let strategy = component
+            .iter()
+            .filter(|v| game.owner(**v) == player)
+            .map(|v| (*v, *strategy.successor(v).unwrap()))
+            .collect();
+
+        tangles.push(Tangle {
+            player,
+            nodes: component,
+            strategy: PositionalStrategy(strategy),
+            escapes
+        });
+    }
+
+    tangles
+}
+
+/// Searches the remaining game for a dominion, using the tangles learned so far.
+///
+/// The game is decomposed into regions as in Zielonka's algorithm, starting at the most significant (lowest) priority,
+/// where every region is the tangle attractor of the player of its priority. The tangles of every region are
+/// extracted, and a tangle without escapes is a dominion.
+fn search(game: &Game, remaining: &HashSet<u32>, tangles: &[Tangle]) -> Search {
+    let mut subgame = remaining.clone();
+    let mut found = Vec::new();
+
+    while let Some(p) = subgame.iter().map(|v| game.prio(*v)).min() {
+        let player = Owner::of_prio(p);
+        let top = subgame
+            .iter()
+            .filter(|v| game.prio(**v) == p)
+            .cloned()
+            .collect::<HashSet<u32>>();
+        let mut a = tangle_attractor(game, player, &subgame, &top, tangles);
+
+        // The player may move from the top nodes to any successor within the region.
+        for &v in &top {
+            if game.owner(v) == player && !a.strategy.0.contains_key(&v) {
+                if let Some(w) = game.succ(v).iter().find(|w| a.nodes.contains(w)) {
+                    a.strategy.0.insert(v, *w);
+                }
+            }
+        }
+
+        for t in extract_tangles(game, player, remaining, &subgame, &a.nodes, &a.strategy) {
+            if t.escapes.is_empty() {
+                return Search::Dominion(t);
+            }
+
+            found.push(t);
+        }
+
+        for v in &a.nodes {
+            subgame.remove(v);
+        }
+    }
+
+    Search::Tangles(found)
+}
+
+/// Solves the game using tangle learning, as by van Dijk.
+///
+/// Every search over the remaining game either finds a dominion, of which the attractor is removed from the game, or
+/// learns new tangles which are attracted in the next searches. Every node in the game must have a successor. The token
+/// is checked before every search. Once it is cancelled, the statistics so far are returned instead.
+pub fn tangle_learning(game: &Game, cancel: &Cancellation) -> Result<TangleLearningResult, TangleLearningTimedOut> {
+    let mut remaining = game.nodes().collect::<HashSet<u32>>();
+    let mut tangles = Vec::new();
+    let mut result = TangleLearningResult {
+        won_even: HashSet::new(),
+        won_odd: HashSet::new(),
+        strategy_even: PositionalStrategy::new(),
+        strategy_odd: PositionalStrategy::new(),
+        nr_of_iterations: 0,
+        tangle_sizes: Vec::new()
+    };
+
+    while !remaining.is_empty() {
+        if cancel.is_cancelled() {
+            return Err(TangleLearningTimedOut {
+                nr_of_iterations: result.nr_of_iterations,
+                nr_of_tangles: result.tangle_sizes.len() as u64
+            });
+        }
+
+        result.nr_of_iterations += 1;
+
+        let dominion = match search(game, &remaining, &tangles) {
+            Search::Dominion(dominion) => dominion,
+            Search::Tangles(found)     => {
+                result.tangle_sizes.extend(found.iter().map(|t| t.nodes.len()));
+                tangles.extend(found);
+                continue;
+            }
+        };
+
+        let a = tangle_attractor(game, dominion.player, &remaining, &dominion.nodes, &tangles);
+        let (won, strategy) = match dominion.player {
+            Owner::Even => (&mut result.won_even, &mut result.strategy_even),
+            Owner::Odd  => (&mut result.won_odd, &mut result.strategy_odd),
+        };
+        strategy.0.extend(dominion.strategy.0);
+        strategy.0.extend(a.strategy.0);
+
+        for v in &a.nodes {
+            remaining.remove(v);
+        }
+        won.extend(a.nodes);
+
+        // Only keep the tangles that lie entirely within the remaining game.
+        tangles.retain(|t| t.nodes.is_subset(&remaining));
+        for t in &mut tangles {
+            t.escapes.retain(|w| remaining.contains(w));
+        }
+    }
+
+    Ok(result)
+}
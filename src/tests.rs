@@ -1,28 +1,687 @@
+use std::collections::HashSet;
+use std::time::Duration;
 
+use pg::*;
+use algorithm;
+use algorithm::ProgSort;
+use arena;
+use arena::Arena;
+use cancel::Cancellation;
+use parser;
+use parser::ParseErrorKind;
+use preprocess;
+use preprocess::Size;
+use resolving;
+use resolving::Edit;
+use solver;
+use solver::{Options, Solver};
+use strategies;
+use fixpoint::ResetSort;
+use local;
+use strategy_improvement::ImprovementRule;
+use verify;
+use verify::VerifyError;
+use writer;
+use zielonka;
+
+/// The game files of which the solutions are compared.
+const GAMES: &[&str] = &[
+    "tests/test1.gm",
+    "tests/test2.gm",
+    "tests/test3.gm",
+    "tests/test4.gm",
+    "tests/test5.gm",
+    "tests/test6.gm",
+    "tests/test7.gm",
+    "tests/test8.gm"
+];
+
+fn set(nodes: &[u32]) -> HashSet<u32> {
+    nodes.iter().cloned().collect()
+}
+
+fn strategy(choices: &[(u32, u32)]) -> PositionalStrategy {
+    PositionalStrategy(choices.iter().cloned().collect())
+}
+
+/// Returns the successors of the node with the identifier, by their identifiers.
+fn succ_ids(game: &Game, id: u32) -> HashSet<u32> {
+    game.succ(game.index(id).unwrap()).iter().map(|w| game.id(*w)).collect()
+}
+
+/// Generates a game from the seed, of which the nodes are divided into parts without edges between them.
+///
+/// The nodes are numbered such that the part of a node is its identifier modulo the number of parts, and every node
+/// has one to three successors within its part.
+fn random_game(seed: u64, nr_of_nodes: u32, nr_of_parts: u32, max_prio: u32) -> Game {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    let mut next = move |bound: u32| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as u32
+    };
+
+    let mut input = format!("parity {};\n", nr_of_nodes - 1);
+    for v in 0..nr_of_nodes {
+        let part_size = (nr_of_nodes - v % nr_of_parts).div_ceil(nr_of_parts);
+        let succ = (0..1 + next(3))
+            .map(|_| (next(part_size) * nr_of_parts + v % nr_of_parts).to_string())
+            .collect::<Vec<String>>();
+        input.push_str(&format!("{} {} {} {};\n", v, next(max_prio + 1), next(2), succ.join(",")));
+    }
+
+    parser::parse(&input).unwrap()
+}
 
 #[test]
 fn parsing() {
-    let input = "parity 4;\n1 3 0 1,3,4 \"Europe\";\n0 6 1 4,2;\n4 5 1 0 \"Antarctica\";\n1 8 1 2,4,3 \"America\";\n3 6 0 4,2 \"Australia\";\n2 7 0 3,1,0,4 \"Asia\";";
-    
-    let parsed = parser::parse(pg);;
-    let result = Game({
-        Node { id: 0, prio: 6, owner: Odd, succ: {2, 4}, name: Some("Africa") }, 
-        Node { id: 3, prio: 6, owner: Even, succ: {2, 4}, name: Some("Australia") }, 
-        Node { id: 1, prio: 3, owner: Even, succ: {1, 3, 4}, name: Some("Europe") }, 
-        Node { id: 4, prio: 5, owner: Odd, succ: {0}, name: Some("Antarctica") }, 
-        Node { id: 2, prio: 7, owner: Even, succ: {1, 0, 4, 3}, name: Some("Asia") }
-    });
-    assert_eq!(parsed, result);
-
-    let result = Game({});
-    assert_ne!(parsed, result);
-
-    let result = Game({
-        Node { id: 0, prio: 5, owner: Odd, succ: {2, 4}, name: Some("Africa") }, 
-        Node { id: 3, prio: 6, owner: Even, succ: {2, 4}, name: Some("Australia") }, 
-        Node { id: 1, prio: 2, owner: Even, succ: {1, 3, 4}, name: Some("Europe") }, 
-        Node { id: 4, prio: 3, owner: Odd, succ: {0}, name: Some("Antarctica") }, 
-        Node { id: 2, prio: 7, owner: Even, succ: {1, 0, 4, 3}, name: Some("Asia") }
-    });
-    assert_ne!(parsed, result);
-}
\ No newline at end of file
+    let input = "parity 4;\n1 3 0 1,3,4 \"Europe\";\n0 6 1 4,2 \"Africa\";\n4 5 1 0 \"Antarctica\";\n3 6 0 4,2 \"Australia\";\n2 7 0 3,1,0,4 \"Asia\";";
+
+    let game = parser::parse(input).unwrap();
+    assert_eq!(game.len(), 5);
+    assert_eq!(game.max_prio(), 7);
+    assert!(game.validate().is_valid());
+
+    let expected = [
+        (0, 6, Owner::Odd, set(&[2, 4])),
+        (1, 3, Owner::Even, set(&[1, 3, 4])),
+        (2, 7, Owner::Even, set(&[0, 1, 3, 4])),
+        (3, 6, Owner::Even, set(&[2, 4])),
+        (4, 5, Owner::Odd, set(&[0]))
+    ];
+
+    for &(id, prio, owner, ref succ) in &expected {
+        let v = game.index(id).unwrap();
+        assert_eq!(game.prio(v), prio);
+        assert_eq!(game.owner(v), owner);
+        assert_eq!(&succ_ids(&game, id), succ);
+    }
+
+    // The nodes are indexed in the order in which they were specified.
+    assert_eq!(game.nodes().map(|v| game.id(v)).collect::<Vec<u32>>(), vec![1, 0, 4, 3, 2]);
+}
+
+#[test]
+fn parse_errors() {
+    let cases = vec![
+        ("parity x;\n0 1 0 0;", 1, ParseErrorKind::InvalidHeader),
+        ("parity 1;\na 1 0 0;", 2, ParseErrorKind::InvalidId),
+        ("parity 1;\n0;", 2, ParseErrorKind::MissingPriority),
+        ("parity 1;\n0 -1 0 0;", 2, ParseErrorKind::InvalidPriority),
+        ("parity 1;\n0 1;", 2, ParseErrorKind::MissingOwner),
+        ("parity 1;\n0 1 2 0;", 2, ParseErrorKind::BadOwner),
+        ("parity 1;\n0 1 0;", 2, ParseErrorKind::MissingSuccessors),
+        ("parity 1;\n0 1 0 0,a;", 2, ParseErrorKind::InvalidSuccessor("a".to_string())),
+        ("parity 1;\n0 1 0 0 \"\";", 2, ParseErrorKind::InvalidName),
+        ("parity 1;\n0 1 0 0;\n1 2 1 0;\n0 2 1 1;", 4, ParseErrorKind::DuplicateId(0)),
+        ("parity 1;\n0 1 0 0;\n2 2 1 0;", 3, ParseErrorKind::HeaderMismatch { max_id: 1, id: 2 }),
+        ("parity 1;\n0 1 0 1;\n1 2 1 5;", 3, ParseErrorKind::UnknownSuccessor(5)),
+        ("parity 1;\n0 1 0 7,1;\n1 2 1 5,0;", 2, ParseErrorKind::UnknownSuccessor(7))
+    ];
+
+    for (input, line, kind) in cases {
+        let err = parser::parse(input).unwrap_err();
+        assert_eq!((err.line, err.kind), (line, kind), "Parsing '{}'", input);
+    }
+}
+
+#[test]
+fn parse_error_message() {
+    let err = parser::parse("parity 1; 0 1 0 1; 1 2 1 5;").unwrap_err();
+    assert_eq!(err.to_string(), "Line 1: The successor 5 is not a node of the game. In: '1 2 1 5'");
+}
+
+#[test]
+fn parse_lenient_unknown_successor() {
+    let input = "parity 2;\n0 1 0 1;\n1 2 1 5,0;\n2 3 0 6;";
+
+    let game = parser::parse_lenient(input).unwrap();
+    assert_eq!(succ_ids(&game, 1), set(&[0]));
+    assert!(game.succ(game.index(2).unwrap()).is_empty());
+
+    let validation = game.validate();
+    assert_eq!(validation.dangling_edges, vec![(1, 5), (2, 6)]);
+    assert_eq!(validation.dead_ends, vec![2]);
+
+    // The dead end gets a self-loop that is losing for its owner.
+    let game = game.repair();
+    assert!(game.validate().is_valid());
+    let v = game.index(2).unwrap();
+    assert_eq!(game.succ(v), &[v]);
+    assert_eq!(game.prio(v), 3);
+}
+
+#[test]
+fn parse_edits() {
+    let game = parser::parse("parity 2;\n0 1 0 1;\n1 2 1 0;\n2 3 0 0;").unwrap();
+    let index = |id| game.index(id).unwrap();
+
+    let edits = parser::parse_edits(&game, "add 0 2;\nremove 1 0;\n\nprio 2 4;\nowner 0 1;").unwrap();
+    assert_eq!(edits, vec![
+        Edit::AddEdge(index(0), index(2)),
+        Edit::RemoveEdge(index(1), index(0)),
+        Edit::SetPrio(index(2), 4),
+        Edit::SetOwner(index(0), Owner::Odd)
+    ]);
+
+    let cases = vec![
+        ("add 0 3;", 1, ParseErrorKind::UnknownId(3)),
+        ("add 0 2;\nprio 4 1;", 2, ParseErrorKind::UnknownId(4)),
+        ("swap 0 1;", 1, ParseErrorKind::InvalidEdit),
+        ("add 0;", 1, ParseErrorKind::InvalidEdit),
+        ("prio 0 x;", 1, ParseErrorKind::InvalidEdit),
+        ("owner 0 2;", 1, ParseErrorKind::InvalidEdit),
+        ("remove 0 1 2;", 1, ParseErrorKind::InvalidEdit)
+    ];
+
+    for (input, line, kind) in cases {
+        let err = parser::parse_edits(&game, input).unwrap_err();
+        assert_eq!((err.line, err.kind), (line, kind), "Parsing '{}'", input);
+    }
+}
+
+#[test]
+fn verification() {
+    // Even wins 0 by staying there and 2 by moving to 0, Odd wins 1 by staying there.
+    let game = parser::parse("parity 2;\n0 2 0 0,1;\n1 1 1 0,1;\n2 1 0 0,2;").unwrap();
+    let won_even = set(&[0, 2]);
+    let won_odd = set(&[1]);
+    let strategy_even = strategy(&[(0, 0), (2, 0)]);
+    let strategy_odd = strategy(&[(1, 1)]);
+
+    assert_eq!(verify::verify(&game, &won_even, &won_odd, &strategy_even, &strategy_odd), Ok(()));
+
+    let cases = vec![
+        (set(&[0, 2, 5]), set(&[1]), strategy_even.clone(), strategy_odd.clone(), VerifyError::UnknownNode(5)),
+        (set(&[0]), set(&[1]), strategy_even.clone(), strategy_odd.clone(), VerifyError::Unclaimed(2)),
+        (set(&[0, 2]), set(&[1, 2]), strategy_even.clone(), strategy_odd.clone(), VerifyError::ClaimedByBoth(2)),
+        (set(&[0, 2]), set(&[1]), strategy(&[(0, 0)]), strategy_odd.clone(),
+         VerifyError::MissingChoice(Owner::Even, 2)),
+        (set(&[0, 2]), set(&[1]), strategy(&[(0, 1), (2, 0)]), strategy_odd.clone(),
+         VerifyError::InvalidChoice(Owner::Even, 0, 1)),
+        (set(&[0]), set(&[1, 2]), strategy(&[(0, 0)]), strategy_odd.clone(), VerifyError::Escape(Owner::Odd, 2, 0)),
+        (set(&[0, 2]), set(&[1]), strategy(&[(0, 0), (2, 2)]), strategy_odd.clone(),
+         VerifyError::LosingCycle(Owner::Even, vec![2]))
+    ];
+
+    for (won_even, won_odd, strategy_even, strategy_odd, expected) in cases {
+        assert_eq!(verify::verify(&game, &won_even, &won_odd, &strategy_even, &strategy_odd), Err(expected));
+    }
+}
+
+#[test]
+fn solvers_agree() {
+    let mut variants = solver::NAMES
+        .iter()
+        .map(|name| (name.to_string(), Options::default()))
+        .collect::<Vec<(String, Options)>>();
+    variants.push(("spm".to_string(), Options { player: None, ..Options::default() }));
+    variants.push(("spm".to_string(), Options { player: Some(Owner::Odd), ..Options::default() }));
+    variants.push(("fpi".to_string(), Options { reset_sort: ResetSort::Justified, ..Options::default() }));
+    variants.push(("si".to_string(), Options { rule: ImprovementRule::SingleSwitch, ..Options::default() }));
+    variants.push(("si".to_string(), Options { rule: ImprovementRule::RandomFacet, ..Options::default() }));
+
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let expected = solver::by_name("zielonka", &Options::default()).unwrap().solve(&game);
+
+        for (name, options) in &variants {
+            let solution = solver::by_name(name, options).unwrap().solve(&game);
+
+            assert_eq!(solution.won_even, expected.won_even, "{} on '{}' with {:?}", name, file, options);
+            assert_eq!(solution.won_odd, expected.won_odd, "{} on '{}' with {:?}", name, file, options);
+            assert_eq!(verify::verify(&game,
+                                      &solution.won_even,
+                                      &solution.won_odd,
+                                      &solution.strategy_even,
+                                      &solution.strategy_odd),
+                       Ok(()),
+                       "{} on '{}' with {:?}", name, file, options);
+        }
+    }
+}
+
+#[test]
+fn attractor_strategies() {
+    // The nodes are specified in the order of their identifiers, so their indices equal their identifiers.
+    let game = parser::parse("parity 5;\n0 0 0 1,2;\n1 0 1 0,3;\n2 0 0 3;\n3 1 1 3;\n4 0 1 2,4;\n5 0 0 4;").unwrap();
+    let all = set(&[0, 1, 2, 3, 4, 5]);
+
+    let cases = vec![
+        (Owner::Even, all.clone(), set(&[3]), set(&[0, 1, 2, 3]), strategy(&[(0, 2), (2, 3)])),
+        (Owner::Even, set(&[1, 2, 3, 4, 5]), set(&[3]), set(&[1, 2, 3]), strategy(&[(2, 3)])),
+        (Owner::Odd, all.clone(), set(&[4]), set(&[4, 5]), strategy(&[])),
+        (Owner::Odd, all.clone(), set(&[3]), all.clone(), strategy(&[(1, 3), (4, 2)]))
+    ];
+
+    for (owner, subgame, target, nodes, expected) in cases {
+        let attractor = game.attractor(owner, &subgame, &target);
+        assert_eq!(attractor.nodes, nodes, "{:?} to {:?} in {:?}", owner, target, subgame);
+        assert_eq!(attractor.strategy, expected, "{:?} to {:?} in {:?}", owner, target, subgame);
+    }
+}
+
+#[test]
+fn write_solution() {
+    // The nodes are specified out of the order of their identifiers, in which the solution lists them.
+    let game = parser::parse("parity 2;\n2 1 0 0,2;\n0 2 0 0,1;\n1 1 1 0,1;").unwrap();
+    let index = |id| game.index(id).unwrap();
+    let won_odd = [index(1)].iter().cloned().collect();
+    let strategy_even = strategy(&[(index(0), index(0)), (index(2), index(0))]);
+    let strategy_odd = strategy(&[(index(1), index(1))]);
+
+    assert_eq!(writer::write(&game, &won_odd, &strategy_even, &strategy_odd),
+               "paritysol 2;\n0 0 0;\n1 1 1;\n2 0 0;\n");
+
+    // A node without a choice of its winner is written without a successor.
+    assert_eq!(writer::write(&game, &won_odd, &strategy_even, &PositionalStrategy::new()),
+               "paritysol 2;\n0 0 0;\n1 1;\n2 0 0;\n");
+}
+
+#[test]
+fn spm_worklist() {
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let strategy = strategies::by_name("input", &game).unwrap();
+
+        for &player in &[Owner::Even, Owner::Odd] {
+            let cancel = Cancellation::new();
+            let swept = algorithm::small_progress_measures(&game, &*strategy, ProgSort::ClosedForm, player, &cancel)
+                .unwrap();
+            let listed = algorithm::small_progress_measures_worklist(&game,
+                                                                     &*strategy,
+                                                                     ProgSort::ClosedForm,
+                                                                     player,
+                                                                     &cancel)
+                .unwrap();
+
+            // Both reach the least fixpoint, which is unique.
+            let (swept, listed) = match player {
+                Owner::Even => (swept.progress_even.unwrap(), listed.progress_even.unwrap()),
+                Owner::Odd  => (swept.progress_odd.unwrap(), listed.progress_odd.unwrap()),
+            };
+            for v in game.nodes() {
+                assert_eq!(swept.measure(v), listed.measure(v), "{:?} on '{}' at {}", player, file, game.id(v));
+            }
+        }
+
+        let options = Options { worklist: true, ..Options::default() };
+        let solution = solver::by_name("spm", &options).unwrap().solve(&game);
+        assert_eq!(verify::verify(&game,
+                                  &solution.won_even,
+                                  &solution.won_odd,
+                                  &solution.strategy_even,
+                                  &solution.strategy_odd),
+                   Ok(()),
+                   "'{}'", file);
+    }
+}
+
+#[test]
+fn spm_incremental() {
+    let closed_form = Options::default();
+    let incremental = Options { prog_sort: ProgSort::Incremental, ..Options::default() };
+
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let expected = solver::by_name("spm", &closed_form).unwrap().solve(&game);
+        assert_eq!(expected.statistics["Number of prog steps"], 0, "'{}'", file);
+
+        // The steps are counted by every solve on its own, so solving again counts the same number of steps.
+        let steps = (0..2)
+            .map(|_| {
+                let solution = solver::by_name("spm", &incremental).unwrap().solve(&game);
+                assert_eq!(solution.won_even, expected.won_even, "'{}'", file);
+                assert_eq!(solution.strategy_even, expected.strategy_even, "'{}'", file);
+                solution.statistics["Number of prog steps"]
+            })
+            .collect::<Vec<u64>>();
+        assert!(steps[0] > 0, "'{}'", file);
+        assert_eq!(steps[0], steps[1], "'{}'", file);
+    }
+}
+
+#[test]
+fn decomposition() {
+    // The components are {4}, {3}, {1, 2} and {0}, of which {3} is attracted by Odd after {4} is solved.
+    let game = parser::parse("parity 4;\n0 2 0 0,1;\n1 1 1 2;\n2 3 0 1,3;\n3 0 1 3,4;\n4 5 1 4;").unwrap();
+    let components = game.sccs(&game.nodes().collect());
+    assert_eq!(components, vec![set(&[4]), set(&[3]), set(&[1, 2]), set(&[0])]);
+
+    let decomposition = solver::Decomposition { inner: solver::by_name("zielonka", &Options::default()).unwrap() };
+    let solution = decomposition.solve(&game);
+    assert_eq!(solution.won_even, set(&[0]));
+    assert_eq!(solution.won_odd, set(&[1, 2, 3, 4]));
+    assert_eq!(solution.statistics["Number of components"], 4);
+    assert_eq!(solution.statistics["Number of solved components"], 3);
+    assert_eq!(verify::verify(&game,
+                              &solution.won_even,
+                              &solution.won_odd,
+                              &solution.strategy_even,
+                              &solution.strategy_odd),
+               Ok(()));
+
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let expected = solver::by_name("zielonka", &Options::default()).unwrap().solve(&game);
+
+        for name in solver::NAMES {
+            let decomposition = solver::Decomposition { inner: solver::by_name(name, &Options::default()).unwrap() };
+            let solution = decomposition.solve(&game);
+
+            assert_eq!(solution.won_even, expected.won_even, "{} on '{}'", name, file);
+            assert_eq!(verify::verify(&game,
+                                      &solution.won_even,
+                                      &solution.won_odd,
+                                      &solution.strategy_even,
+                                      &solution.strategy_odd),
+                       Ok(()),
+                       "{} on '{}'", name, file);
+        }
+    }
+}
+
+#[test]
+fn preprocessing() {
+    // Even wins the self-loop of 0 and Odd the only edge of 3. The priorities of the cycles 1, 2 and 4, 5 are
+    // propagated to 1 and 6, and then compressed to 1 and 2.
+    let game = parser::parse("parity 5;\n0 2 0 0,1;\n1 1 1 0,2;\n2 4 0 1,3;\n3 7 1 3;\n4 6 0 5;\n5 8 1 4;")
+        .unwrap();
+    let (reduced, result) = preprocess::preprocess(&game);
+
+    assert_eq!(result.won_even, set(&[0]));
+    assert_eq!(result.won_odd, set(&[3]));
+    assert_eq!(result.strategy_even, strategy(&[(0, 0)]));
+    assert_eq!(reduced.nodes().map(|v| (reduced.id(v), reduced.prio(v))).collect::<HashSet<(u32, u32)>>(),
+               [(1, 1), (2, 1), (4, 2), (5, 2)].iter().cloned().collect());
+
+    let size = |nodes, edges, prios, max_prio| Size { nodes, edges, prios, max_prio };
+    let reductions = result.reductions.iter().map(|r| (r.step, r.before, r.after)).collect::<Vec<_>>();
+    assert_eq!(reductions, vec![
+        ("Self-loops", size(6, 9, 6, 8), size(4, 4, 4, 8)),
+        ("Propagation", size(4, 4, 4, 8), size(4, 4, 2, 6)),
+        ("Compression", size(4, 4, 2, 6), size(4, 4, 2, 2))
+    ]);
+
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let expected = solver::by_name("zielonka", &Options::default()).unwrap().solve(&game);
+
+        for name in solver::NAMES {
+            let preprocessing = solver::Preprocessing { inner: solver::by_name(name, &Options::default()).unwrap() };
+            let solution = preprocessing.solve(&game);
+
+            assert_eq!(solution.won_even, expected.won_even, "{} on '{}'", name, file);
+            assert_eq!(verify::verify(&game,
+                                      &solution.won_even,
+                                      &solution.won_odd,
+                                      &solution.strategy_even,
+                                      &solution.strategy_odd),
+                       Ok(()),
+                       "{} on '{}'", name, file);
+        }
+    }
+}
+
+#[test]
+fn cancellation() {
+    // Without self-loops, preprocessing leaves the game to the inner solver.
+    let game = parser::parse("parity 2;\n0 1 0 1;\n1 2 1 2,0;\n2 3 0 0;").unwrap();
+    let cancelled = Cancellation::with_timeout(Duration::from_millis(0));
+    let later = Cancellation::with_timeout(Duration::from_secs(60 * 60));
+    assert!(cancelled.is_cancelled());
+    let expected = solver::by_name("zielonka", &Options::default()).unwrap().solve(&game);
+
+    for name in solver::NAMES {
+        let solvers: Vec<Box<dyn Solver>> = vec![
+            solver::by_name(name, &Options::default()).unwrap(),
+            Box::new(solver::Decomposition { inner: solver::by_name(name, &Options::default()).unwrap() }),
+            Box::new(solver::Preprocessing { inner: solver::by_name(name, &Options::default()).unwrap() })
+        ];
+
+        for solver in solvers {
+            assert!(solver.solve_cancellable(&game, &cancelled).is_err(), "{}", name);
+
+            let solution = solver.solve_cancellable(&game, &later).unwrap();
+            assert_eq!(solution.won_even, expected.won_even, "{}", name);
+        }
+    }
+
+    // The statistics so far are returned, here those of the sweep that was cancelled before its first node.
+    let timed_out = solver::by_name("spm", &Options::default()).unwrap().solve_cancellable(&game, &cancelled).err();
+    assert_eq!(timed_out.unwrap().statistics["Number of iterations"], 1);
+}
+
+#[test]
+fn spm_threads() {
+    let mut games = GAMES.iter().map(|file| parser::parse_from_file(file).unwrap()).collect::<Vec<Game>>();
+    games.extend((0..4).map(|seed| random_game(seed, 200, 1, 6)));
+
+    for (i, game) in games.iter().enumerate() {
+        let strategy = strategies::by_name("input", game).unwrap();
+        let expected = solver::by_name("zielonka", &Options::default()).unwrap().solve(game);
+
+        for &player in &[Owner::Even, Owner::Odd] {
+            let cancel = Cancellation::new();
+            let swept = algorithm::small_progress_measures(game, &*strategy, ProgSort::ClosedForm, player, &cancel)
+                .unwrap();
+
+            for &threads in &[2, 4] {
+                let parallel = algorithm::small_progress_measures_parallel(game,
+                                                                           &*strategy,
+                                                                           ProgSort::ClosedForm,
+                                                                           player,
+                                                                           threads,
+                                                                           &cancel)
+                    .unwrap();
+
+                // The threads reach the least fixpoint, no matter in which order they lift the nodes.
+                let (swept, parallel) = match player {
+                    Owner::Even => (swept.progress_even.as_ref().unwrap(), parallel.progress_even.unwrap()),
+                    Owner::Odd  => (swept.progress_odd.as_ref().unwrap(), parallel.progress_odd.unwrap()),
+                };
+                for v in game.nodes() {
+                    assert_eq!(swept.measure(v), parallel.measure(v), "{:?}, {} threads, game {}", player, threads, i);
+                }
+            }
+        }
+
+        let options = Options { threads: 4, ..Options::default() };
+        let solution = solver::by_name("spm", &options).unwrap().solve(game);
+        assert_eq!(solution.won_even, expected.won_even, "game {}", i);
+        assert_eq!(verify::verify(game,
+                                  &solution.won_even,
+                                  &solution.won_odd,
+                                  &solution.strategy_even,
+                                  &solution.strategy_odd),
+                   Ok(()),
+                   "game {}", i);
+    }
+}
+
+#[test]
+fn zielonka_threads() {
+    // The game and its parts are larger than the least number of nodes for which several threads are used.
+    let game = random_game(1, 6000, 3, 6);
+    let expected = zielonka::zielonka(&game);
+    let solution = zielonka::zielonka_parallel(&game, 4, &Cancellation::new()).unwrap();
+
+    assert!(solution.nr_of_parallel_subgames > 0);
+    assert_eq!(solution.won_even, expected.won_even);
+    assert_eq!(solution.won_odd, expected.won_odd);
+    assert_eq!(verify::verify(&game,
+                              &solution.won_even,
+                              &solution.won_odd,
+                              &solution.strategy_even,
+                              &solution.strategy_odd),
+               Ok(()));
+
+    let all = game.nodes().collect::<HashSet<u32>>();
+    for &owner in &[Owner::Even, Owner::Odd] {
+        let target = game.nodes().filter(|v| game.prio(*v) == 0).collect::<HashSet<u32>>();
+        let a = game.attractor(owner, &all, &target);
+        let parallel = game.attractor_parallel(owner, &all, &target, 4);
+        assert_eq!(parallel.nodes, a.nodes, "{:?}", owner);
+
+        // The choices may differ, but the owner must still force every play from the attractor into the target.
+        let mut forced = target.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &v in &parallel.nodes {
+                let attracted = if game.owner(v) == owner {
+                    parallel.strategy.successor(&v).is_some_and(|w| game.succ(v).contains(w) && forced.contains(w))
+                } else {
+                    game.succ(v).iter().all(|w| forced.contains(w))
+                };
+                if !forced.contains(&v) && attracted {
+                    forced.insert(v);
+                    changed = true;
+                }
+            }
+        }
+        assert_eq!(forced, parallel.nodes, "{:?}", owner);
+    }
+}
+
+/// A ring of a trillion nodes that is entered from a short path, which is far too large to explore entirely.
+///
+/// Node 0 to 3 form a path to node 4, where `Even` may stay forever or enter the ring at node 5. The ring is only
+/// visited at priority 1.
+struct Ring;
+
+impl Arena for Ring {
+    type Node = u64;
+    type Successors<'a> = ::std::vec::IntoIter<u64>;
+
+    fn prio(&self, v: u64) -> u32 {
+        if v == 4 { 0 } else { 1 }
+    }
+
+    fn owner(&self, v: u64) -> Owner {
+        if v.is_multiple_of(2) { Owner::Even } else { Owner::Odd }
+    }
+
+    fn successors(&self, v: u64) -> Self::Successors<'_> {
+        match v {
+            4                 => vec![4, 5],
+            v if v < 4        => vec![v + 1],
+            1_000_000_000_000 => vec![5],
+            v                 => vec![v + 1],
+        }.into_iter()
+    }
+
+    fn initial(&self) -> Option<u64> {
+        Some(0)
+    }
+}
+
+#[test]
+fn local_solving() {
+    for file in GAMES {
+        let game = parser::parse_from_file(file).unwrap();
+        let expected = zielonka::zielonka(&game);
+
+        for v in game.nodes() {
+            let result = local::solve_local(&game, v);
+            let won = match result.winner {
+                Owner::Even => &expected.won_even,
+                Owner::Odd  => &expected.won_odd,
+            };
+            assert!(won.contains(&v), "{}: node {}", file, game.id(v));
+            assert!(result.won.is_subset(won), "{}: node {}", file, game.id(v));
+
+            // The winner stays in the nodes it won, whatever its opponent does.
+            for &w in &result.won {
+                let stays = if game.owner(w) == result.winner {
+                    result.strategy.successor(&w).is_some_and(|x| game.succ(w).contains(x) && result.won.contains(x))
+                } else {
+                    game.succ(w).iter().all(|x| result.won.contains(x))
+                };
+                assert!(stays, "{}: node {} at {}", file, game.id(v), game.id(w));
+            }
+        }
+    }
+
+    // Only a small part of the ring is explored before node 0 is decided.
+    let result = local::solve_local(&Ring, Ring.initial().unwrap());
+    assert_eq!(result.winner, Owner::Even);
+    assert_eq!(result.strategy.successor(&4), Some(&4));
+    assert!(result.won.is_superset(&[0, 1, 2, 3, 4].iter().cloned().collect()));
+    assert!(result.nr_of_explored_nodes < 1000);
+}
+
+#[test]
+fn arena_attractor() {
+    // The same game and cases as in `attractor_strategies`, with the predecessors given by the game.
+    let game = parser::parse("parity 5;\n0 0 0 1,2;\n1 0 1 0,3;\n2 0 0 3;\n3 1 1 3;\n4 0 1 2,4;\n5 0 0 4;").unwrap();
+    let all = set(&[0, 1, 2, 3, 4, 5]);
+    let pred = |v| game.pred(v).to_vec();
+
+    let cases = vec![
+        (Owner::Even, all.clone(), set(&[3]), set(&[0, 1, 2, 3]), strategy(&[(0, 2), (2, 3)])),
+        (Owner::Even, set(&[1, 2, 3, 4, 5]), set(&[3]), set(&[1, 2, 3]), strategy(&[(2, 3)])),
+        (Owner::Odd, all.clone(), set(&[4]), set(&[4, 5]), strategy(&[])),
+        (Owner::Odd, all.clone(), set(&[3]), all.clone(), strategy(&[(1, 3), (4, 2)]))
+    ];
+
+    for (owner, subgame, target, nodes, expected) in cases {
+        let attractor = arena::attractor(&game, owner, &subgame, &target, pred);
+        assert_eq!(attractor.nodes, nodes, "{:?} to {:?} in {:?}", owner, target, subgame);
+        assert_eq!(attractor.strategy, expected, "{:?} to {:?} in {:?}", owner, target, subgame);
+    }
+
+    // A game starts in the node with identifier 0, and an empty game has no initial node.
+    let game = parser::parse("parity 2;\n2 1 0 0;\n1 1 1 0;\n0 2 0 1;").unwrap();
+    assert_eq!(game.initial(), game.index(0));
+    assert_eq!(Game::from_arena(&game, &[]).initial(), None);
+}
+
+#[test]
+fn resolving() {
+    // A game is moved into every solver, so it is loaded again for each.
+    let load = |i: usize| match GAMES.get(i) {
+        Some(file) => parser::parse_from_file(file).unwrap(),
+        None       => random_game((i - GAMES.len()) as u64, 24, 1, 4),
+    };
+
+    for i in 0..GAMES.len() + 2 {
+        for &player in &[Owner::Even, Owner::Odd] {
+            for &prog_sort in &[ProgSort::ClosedForm, ProgSort::Incremental] {
+                let mut solver = resolving::ResolvingSolver::new(load(i), player, prog_sort);
+                solver.solve();
+
+                // Every kind of edit is applied in turn to nodes spread over the game, solving again after each.
+                for j in 0..24 {
+                    let game = solver.game();
+                    let v = (j * 7) % game.len() as u32;
+                    let w = (j * 13 + 1) % game.len() as u32;
+                    let edit = match j % 4 {
+                        0 => Edit::AddEdge(v, w),
+                        1 => Edit::RemoveEdge(v, game.succ(v)[0]),
+                        2 => Edit::SetPrio(v, (game.prio(v) + 1) % (game.max_prio() + 2)),
+                        _ => Edit::SetOwner(v, game.owner(v).opponent()),
+                    };
+                    solver.apply(edit);
+
+                    let result = solver.solve();
+                    let game = solver.game();
+                    let expected = zielonka::zielonka(game);
+                    assert_eq!(result.won_even, expected.won_even, "game {}, {:?} after {:?}", i, player, edit);
+                    assert_eq!(result.won_odd, expected.won_odd, "game {}, {:?} after {:?}", i, player, edit);
+
+                    // The measures only give the strategy of the player, so the opponent plays that of Zielonka.
+                    let (strategy_even, strategy_odd) = match player {
+                        Owner::Even => (&result.strategy_even, &expected.strategy_odd),
+                        Owner::Odd  => (&expected.strategy_even, &result.strategy_odd),
+                    };
+                    assert_eq!(verify::verify(game, &result.won_even, &result.won_odd, strategy_even, strategy_odd),
+                               Ok(()),
+                               "game {}, {:?} after {:?}", i, player, edit);
+                }
+            }
+        }
+    }
+}
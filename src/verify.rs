@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt;
+
+use pg::*;
+use scc;
+
+/// The reason a claimed solution was rejected.
+///
+/// Nodes are referred to by the identifiers they were specified with, except for `UnknownNode`.
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    /// The index of a node in a claimed winning region does not exist in the game.
+    UnknownNode(u32),
+    /// A node of the game is not claimed by either player.
+    Unclaimed(u32),
+    /// A node of the game is claimed by both players.
+    ClaimedByBoth(u32),
+    /// The strategy of the player does not define a choice on one of its nodes in its winning region.
+    MissingChoice(Owner, u32),
+    /// The strategy of the player chooses a node that is not a successor or lies outside its winning region.
+    InvalidChoice(Owner, u32, u32),
+    /// The opponent of the player can leave the winning region of the player along the edge.
+    Escape(Owner, u32, u32),
+    /// A cycle within the winning region of the player that is consistent with its strategy, but won by the opponent.
+    LosingCycle(Owner, Vec<u32>)
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::UnknownNode(v)             => write!(f, "The node with index {} does not exist in the game.", v),
+            VerifyError::Unclaimed(v)               => write!(f, "The node {} is not won by either player.", v),
+            VerifyError::ClaimedByBoth(v)           => write!(f, "The node {} is won by both players.", v),
+            VerifyError::MissingChoice(owner, v)    => write!(f, "The strategy of {:?} does not define a successor for node {}.", owner, v),
+            VerifyError::InvalidChoice(owner, v, w) => write!(f, "The strategy of {:?} moves from node {} to node {}, which is not a successor within its winning region.", owner, v, w),
+            VerifyError::Escape(owner, v, w)        => write!(f, "The opponent of {:?} can escape its winning region from node {} to node {}.", owner, v, w),
+            VerifyError::LosingCycle(owner, ref c)  => write!(f, "The winning region of {:?} contains the cycle {:?}, which is won by its opponent.", owner, c),
+        }
+    }
+}
+
+/// A graph given by the successors of its nodes, such as the winning region of a player with only the edges that
+/// remain when that player follows its strategy.
+pub type Graph = HashMap<u32, Vec<u32>>;
+
+/// Restricts the game to the winning region of the owner and to the successors chosen by its strategy.
+fn restrict(game: &Game, owner: Owner, region: &HashSet<u32>, strategy: &PositionalStrategy) -> Result<Graph, VerifyError> {
+    let mut graph = Graph::new();
+
+    for &v in region {
+        let succ = game.succ(v);
+
+        if game.owner(v) == owner {
+            let w = match strategy.successor(&v) {
+                Some(w) => *w,
+                None    => return Err(VerifyError::MissingChoice(owner, game.id(v))),
+            };
+
+            if !succ.contains(&w) || !region.contains(&w) {
+                // The choice may not even be a node of the game, in which case its index is reported.
+                let w_id = if w < game.len() as u32 { game.id(w) } else { w };
+                return Err(VerifyError::InvalidChoice(owner, game.id(v), w_id));
+            }
+
+            graph.insert(v, vec![w]);
+        } else {
+            if let Some(w) = succ.iter().find(|w| !region.contains(w)) {
+                return Err(VerifyError::Escape(owner, game.id(v), game.id(*w)));
+            }
+
+            graph.insert(v, succ.to_vec());
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Computes the strongly connected components of the graph restricted to the provided nodes.
+pub fn sccs(graph: &Graph, nodes: &HashSet<u32>) -> Vec<HashSet<u32>> {
+    scc::tarjan(nodes, |v| &graph[&v][..])
+}
+
+/// Finds a cycle through the start node that stays within the provided strongly connected component.
+fn find_cycle(graph: &Graph, component: &HashSet<u32>, start: u32) -> Vec<u32> {
+    let mut parent = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(v) = queue.pop_front() {
+        for w in &graph[&v] {
+            if *w == start {
+                // Walk back from the last node of the cycle to the start.
+                let mut cycle = vec![v];
+                let mut u = v;
+
+                while u != start {
+                    u = parent[&u];
+                    cycle.push(u);
+                }
+
+                cycle.reverse();
+                return cycle;
+            }
+
+            if component.contains(w) && !parent.contains_key(w) {
+                parent.insert(*w, v);
+                queue.push_back(*w);
+            }
+        }
+    }
+
+    unreachable!("The node {} does not lie on a cycle in its component.", start)
+}
+
+/// Checks that every cycle in the graph is won by the owner.
+///
+/// Within a strongly connected component every cycle through a node with the minimal priority is decided by that priority.
+/// If the priority is favourable, the component is searched again without those nodes for cycles avoiding them.
+fn check_cycles(game: &Game, owner: Owner, graph: &Graph) -> Result<(), VerifyError> {
+    let mut pending = vec![graph.keys().cloned().collect::<HashSet<u32>>()];
+
+    while let Some(nodes) = pending.pop() {
+        for component in sccs(graph, &nodes) {
+            // A single node only forms a cycle if it has a self-loop.
+            if component.len() == 1 {
+                let v = component.iter().next().unwrap();
+
+                if !graph[v].contains(v) {
+                    continue;
+                }
+            }
+
+            let min_prio = component.iter().map(|v| game.prio(*v)).min().unwrap();
+
+            if Owner::of_prio(min_prio) != owner {
+                let v = *component.iter().find(|v| game.prio(**v) == min_prio).unwrap();
+                let cycle = find_cycle(graph, &component, v).into_iter().map(|v| game.id(v)).collect();
+                return Err(VerifyError::LosingCycle(owner, cycle));
+            }
+
+            pending.push(component
+                .into_iter()
+                .filter(|v| game.prio(*v) != min_prio)
+                .collect());
+        }
+    }
+
+    Ok(())
+}
+
+/// Verifies a claimed solution of the game.
+///
+/// The claimed winning regions must partition the nodes of the game. For every player, the game is restricted to its
+/// winning region and its strategy, which leaves a one-player game for the opponent. The opponent may not be able to
+/// leave the region, and every cycle it can close must be won by the player.
+pub fn verify(game: &Game,
+              won_even: &HashSet<u32>,
+              won_odd: &HashSet<u32>,
+              strategy_even: &PositionalStrategy,
+              strategy_odd: &PositionalStrategy) -> Result<(), VerifyError> {
+    if let Some(v) = won_even.iter().chain(won_odd.iter()).find(|v| **v >= game.len() as u32) {
+        return Err(VerifyError::UnknownNode(*v));
+    }
+
+    for v in game.nodes() {
+        match (won_even.contains(&v), won_odd.contains(&v)) {
+            (false, false) => return Err(VerifyError::Unclaimed(game.id(v))),
+            (true, true)   => return Err(VerifyError::ClaimedByBoth(game.id(v))),
+            _              => {},
+        }
+    }
+
+    for &(owner, region, strategy) in &[(Owner::Even, won_even, strategy_even), (Owner::Odd, won_odd, strategy_odd)] {
+        let graph = restrict(game, owner, region, strategy)?;
+        check_cycles(game, owner, &graph)?;
+    }
+
+    Ok(())
+}
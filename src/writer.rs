@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::prelude::*;
+
+use pg::Game;
+use pg::PositionalStrategy;
+
+/// Writes the solution of a game in the PGSolver solution format.
+///
+/// The winning set and strategies refer to nodes by their index, the solution by the identifiers of the nodes.
+/// The solution starts with the header 'paritysol <max identifier>;', followed by one line per node in the format:
+/// '<identifier> <winner> [<successor>];'. The successor is the choice of the winner in that node, if it has one.
+pub fn write(game: &Game,
+             won_odd: &HashSet<u32>,
+             strategy_even: &PositionalStrategy,
+             strategy_odd: &PositionalStrategy) -> String {
+    let mut nodes = game.nodes().collect::<Vec<u32>>();
+    nodes.sort_by_key(|v| game.id(*v));
+
+    let max_id = nodes.last().map(|v| game.id(*v)).unwrap_or(0);
+    let mut solution = format!("paritysol {};\n", max_id);
+
+    for v in nodes {
+        let (winner, strategy) = if won_odd.contains(&v) {
+            (1, strategy_odd)
+        } else {
+            (0, strategy_even)
+        };
+
+        match strategy.successor(&v) {
+            Some(w) => solution.push_str(&format!("{} {} {};\n", game.id(v), winner, game.id(*w))),
+            None    => solution.push_str(&format!("{} {};\n", game.id(v), winner)),
+        }
+    }
+
+    solution
+}
+
+/// Writes the solution of a game in the PGSolver solution format to the specified file.
+///
+/// # Panics
+/// - The file cannot be created or written to.
+pub fn write_to_file(file_path: &str,
+                     game: &Game,
+                     won_odd: &HashSet<u32>,
+                     strategy_even: &PositionalStrategy,
+                     strategy_odd: &PositionalStrategy) {
+    let file = File::create(file_path)
+        .unwrap_or_else(|_| panic!("Failed to create the file: '{}'.", file_path));
+
+    let mut buf_writer = BufWriter::new(file);
+    buf_writer
+        .write_all(write(game, won_odd, strategy_even, strategy_odd).as_bytes())
+        .unwrap_or_else(|_| panic!("Failed to write the file: '{}'.", file_path));
+}
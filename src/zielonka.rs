@@ -0,0 +1,333 @@
+use std::cmp;
+use std::collections::{HashSet, VecDeque};
+use std::thread;
+
+use pg::*;
+use cancel::Cancellation;
+
+/// The least number of nodes of a subgame for which attractors and independent parts are computed with several threads.
+const PARALLEL_THRESHOLD: usize = 1024;
+
+pub struct ZielonkaResult {
+    pub won_even: HashSet<u32>,
+    pub won_odd: HashSet<u32>,
+    pub strategy_even: PositionalStrategy,
+    pub strategy_odd: PositionalStrategy,
+    pub nr_of_recursions: u64,
+    /// The deepest level of recursion, where solving the game is the first level.
+    pub max_recursion_depth: u64,
+    pub nr_of_attractors: u64,
+    /// The number of nodes in all attractors together, including their target sets.
+    pub total_attractor_size: u64,
+    pub max_attractor_size: u64,
+    /// The number of parts of subgames that were solved by separate threads.
+    pub nr_of_parallel_subgames: u64
+}
+
+/// The statistics of Zielonka's algorithm that was cancelled before it solved the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZielonkaTimedOut {
+    pub nr_of_recursions: u64,
+    pub nr_of_attractors: u64
+}
+
+/// The counters of a run of the recursive algorithm, which are combined over the threads it used.
+#[derive(Debug, Default)]
+struct Statistics {
+    nr_of_recursions: u64,
+    max_recursion_depth: u64,
+    nr_of_attractors: u64,
+    total_attractor_size: u64,
+    max_attractor_size: u64,
+    nr_of_parallel_subgames: u64
+}
+
+impl Statistics {
+    fn add(&mut self, other: &Statistics) {
+        self.nr_of_recursions += other.nr_of_recursions;
+        self.max_recursion_depth = cmp::max(self.max_recursion_depth, other.max_recursion_depth);
+        self.nr_of_attractors += other.nr_of_attractors;
+        self.total_attractor_size += other.total_attractor_size;
+        self.max_attractor_size = cmp::max(self.max_attractor_size, other.max_attractor_size);
+        self.nr_of_parallel_subgames += other.nr_of_parallel_subgames;
+    }
+}
+
+/// The winning set of a single player within a subgame, together with its winning strategy on that set.
+struct Region {
+    won: HashSet<u32>,
+    strategy: PositionalStrategy
+}
+
+impl Region {
+    fn empty() -> Region {
+        Region {
+            won: HashSet::new(),
+            strategy: PositionalStrategy::new()
+        }
+    }
+
+    /// Adds a region of the same player in a part of the game that is disjoint from this region.
+    fn extend(&mut self, other: Region) {
+        self.won.extend(other.won);
+        self.strategy.0.extend(other.strategy.0);
+    }
+}
+
+/// Orders a pair of (even, odd) regions as (player, opponent) or vice versa.
+///
+/// Swaps the regions if the player is `Odd`, so applying this twice returns the original pair.
+fn orient<T>(player: Owner, pair: (T, T)) -> (T, T) {
+    match player {
+        Owner::Even => pair,
+        Owner::Odd  => (pair.1, pair.0),
+    }
+}
+
+/// Computes the attractor, with several threads if there are any and the subgame is large enough.
+fn attractor(game: &Game,
+             owner: Owner,
+             subgame: &HashSet<u32>,
+             target: &HashSet<u32>,
+             threads: usize,
+             statistics: &mut Statistics) -> Attractor {
+    let a = if threads > 1 && subgame.len() >= PARALLEL_THRESHOLD {
+        game.attractor_parallel(owner, subgame, target, threads)
+    } else {
+        game.attractor(owner, subgame, target)
+    };
+
+    statistics.nr_of_attractors += 1;
+    statistics.total_attractor_size += a.nodes.len() as u64;
+    statistics.max_attractor_size = cmp::max(statistics.max_attractor_size, a.nodes.len() as u64);
+
+    a
+}
+
+/// Divides the subgame into the parts that are not connected to each other by any edge within the subgame.
+fn parts(game: &Game, subgame: &HashSet<u32>) -> Vec<HashSet<u32>> {
+    let mut visited = HashSet::new();
+    let mut parts = Vec::new();
+
+    for &root in subgame {
+        if !visited.insert(root) {
+            continue;
+        }
+
+        let mut part = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+
+        while let Some(v) = queue.pop_front() {
+            part.insert(v);
+
+            for &w in game.succ(v).iter().chain(game.pred(v)) {
+                if subgame.contains(&w) && visited.insert(w) {
+                    queue.push_back(w);
+                }
+            }
+        }
+
+        parts.push(part);
+    }
+
+    parts
+}
+
+/// Solves the subgame as `solve` does, but solves its parts that are not connected to each other in separate
+/// threads if there are any and the subgame is large enough.
+///
+/// A play never moves between parts, so the solution of the subgame is the union of the solutions of its parts. The
+/// parts are divided over the threads from large to small, and every thread passes on its share of the threads.
+/// Returns `None` if the token was cancelled in any of the threads.
+fn solve_parts(game: &Game,
+               subgame: &HashSet<u32>,
+               depth: u64,
+               threads: usize,
+               cancel: &Cancellation,
+               statistics: &mut Statistics) -> Option<(Region, Region)> {
+    if threads <= 1 || subgame.len() < PARALLEL_THRESHOLD {
+        return solve(game, subgame, depth, threads, cancel, statistics);
+    }
+
+    let mut parts = parts(game, subgame);
+    if parts.len() == 1 {
+        return solve(game, subgame, depth, threads, cancel, statistics);
+    }
+
+    statistics.nr_of_parallel_subgames += parts.len() as u64;
+    parts.sort_by_key(|part| cmp::Reverse(part.len()));
+
+    let nr_of_groups = cmp::min(parts.len(), threads);
+    let threads_per_group = threads / nr_of_groups;
+    let mut groups = (0..nr_of_groups).map(|_| Vec::new()).collect::<Vec<Vec<HashSet<u32>>>>();
+    for (i, part) in parts.into_iter().enumerate() {
+        groups[i % nr_of_groups].push(part);
+    }
+
+    let solutions = thread::scope(|scope| {
+        let handles = groups
+            .iter()
+            .map(|group| scope.spawn(move || {
+                let mut statistics = Statistics::default();
+                let mut even = Region::empty();
+                let mut odd = Region::empty();
+
+                for part in group {
+                    match solve(game, part, depth, threads_per_group, cancel, &mut statistics) {
+                        Some((part_even, part_odd)) => {
+                            even.extend(part_even);
+                            odd.extend(part_odd);
+                        },
+                        None                        => return (None, statistics),
+                    }
+                }
+
+                (Some((even, odd)), statistics)
+            }))
+            .collect::<Vec<_>>();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    });
+
+    let mut even = Region::empty();
+    let mut odd = Region::empty();
+    let mut cancelled = false;
+    for (solution, group_statistics) in solutions {
+        statistics.add(&group_statistics);
+
+        match solution {
+            Some((group_even, group_odd)) => {
+                even.extend(group_even);
+                odd.extend(group_odd);
+            },
+            None                          => cancelled = true,
+        }
+    }
+
+    if cancelled {
+        return None;
+    }
+
+    Some((even, odd))
+}
+
+/// Solves the subgame induced by the provided nodes at the depth of recursion and returns the regions of (even, odd).
+///
+/// The token is checked before every recursion, which returns `None` once it is cancelled.
+fn solve(game: &Game,
+         subgame: &HashSet<u32>,
+         depth: u64,
+         threads: usize,
+         cancel: &Cancellation,
+         statistics: &mut Statistics) -> Option<(Region, Region)> {
+    if cancel.is_cancelled() {
+        return None;
+    }
+
+    statistics.nr_of_recursions += 1;
+    statistics.max_recursion_depth = cmp::max(statistics.max_recursion_depth, depth);
+
+    let min_prio = match subgame.iter().map(|v| game.prio(*v)).min() {
+        Some(p) => p,
+        None    => return Some((Region::empty(), Region::empty())),
+    };
+    let player = Owner::of_prio(min_prio);
+    let opponent = player.opponent();
+
+    // Attract to the nodes with the most significant (lowest) priority and solve the remainder.
+    let top = subgame
+        .iter()
+        .filter(|v| game.prio(**v) == min_prio)
+        .cloned()
+        .collect::<HashSet<u32>>();
+    let a = attractor(game, player, subgame, &top, threads, statistics);
+    let rest = subgame.difference(&a.nodes).cloned().collect::<HashSet<u32>>();
+    let solution = solve_parts(game, &rest, depth + 1, threads, cancel, statistics)?;
+    let (mut region_player, region_opponent) = orient(player, solution);
+
+    // The opponent cannot escape anywhere, so the player wins the entire subgame.
+    // It attracts to the top nodes, from where it may move to any successor within the subgame.
+    if region_opponent.won.is_empty() {
+        region_player.won = subgame.clone();
+        region_player.strategy.0.extend(a.strategy.0);
+
+        for &v in &top {
+            if game.owner(v) == player {
+                let w = game.succ(v)
+                    .iter()
+                    .find(|w| subgame.contains(w))
+                    .expect("The subgame contains a node without successors.");
+                region_player.strategy.0.insert(v, *w);
+            }
+        }
+
+        return Some(orient(player, (region_player, Region::empty())));
+    }
+
+    // Remove everything the opponent can attract to its winning set and solve the remainder.
+    let b = attractor(game, opponent, subgame, &region_opponent.won, threads, statistics);
+    let rest = subgame.difference(&b.nodes).cloned().collect::<HashSet<u32>>();
+    let solution = solve_parts(game, &rest, depth + 1, threads, cancel, statistics)?;
+    let (region_player, mut region_opponent_rest) = orient(player, solution);
+    region_opponent_rest.won.extend(b.nodes);
+    region_opponent_rest.strategy.0.extend(region_opponent.strategy.0);
+    region_opponent_rest.strategy.0.extend(b.strategy.0);
+
+    Some(orient(player, (region_player, region_opponent_rest)))
+}
+
+/// Solves the subgame induced by the provided nodes using Zielonka's recursive algorithm with the number of threads.
+///
+/// Every node in the subgame must have a successor within the subgame. The token is checked before every recursion.
+/// Once it is cancelled, the statistics so far are returned instead.
+fn zielonka_threads(game: &Game,
+                    subgame: &HashSet<u32>,
+                    threads: usize,
+                    cancel: &Cancellation) -> Result<ZielonkaResult, ZielonkaTimedOut> {
+    let mut statistics = Statistics::default();
+    let (even, odd) = match solve_parts(game, subgame, 1, threads, cancel, &mut statistics) {
+        Some(solution) => solution,
+        None           => return Err(ZielonkaTimedOut {
+            nr_of_recursions: statistics.nr_of_recursions,
+            nr_of_attractors: statistics.nr_of_attractors
+        }),
+    };
+
+    Ok(ZielonkaResult {
+        won_even: even.won,
+        won_odd: odd.won,
+        strategy_even: even.strategy,
+        strategy_odd: odd.strategy,
+        nr_of_recursions: statistics.nr_of_recursions,
+        max_recursion_depth: statistics.max_recursion_depth,
+        nr_of_attractors: statistics.nr_of_attractors,
+        total_attractor_size: statistics.total_attractor_size,
+        max_attractor_size: statistics.max_attractor_size,
+        nr_of_parallel_subgames: statistics.nr_of_parallel_subgames
+    })
+}
+
+/// Solves the subgame induced by the provided nodes using Zielonka's recursive algorithm.
+///
+/// Every node in the subgame must have a successor within the subgame.
+pub fn zielonka_subgame(game: &Game, subgame: &HashSet<u32>) -> ZielonkaResult {
+    zielonka_threads(game, subgame, 1, &Cancellation::new())
+        .unwrap_or_else(|_| unreachable!("The token is never cancelled."))
+}
+
+/// Solves the game using Zielonka's recursive algorithm.
+pub fn zielonka(game: &Game) -> ZielonkaResult {
+    zielonka_subgame(game, &game.nodes().collect())
+}
+
+/// Solves the game using Zielonka's recursive algorithm with several threads.
+///
+/// Large attractors are computed by all threads together, and the parts of a subgame that are not connected to each
+/// other are solved by separate threads. With a single thread this is the same as `zielonka`. The token is checked
+/// before every recursion, as in `zielonka_threads`.
+pub fn zielonka_parallel(game: &Game,
+                         threads: usize,
+                         cancel: &Cancellation) -> Result<ZielonkaResult, ZielonkaTimedOut> {
+    zielonka_threads(game, &game.nodes().collect(), threads, cancel)
+}